@@ -0,0 +1,103 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{
+    Expr, Ident, Type,
+    parse::{Parse, ParseStream},
+    parse_quote_spanned,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Capture {
+    Location,
+    Backtrace,
+}
+
+impl Capture {
+    /// Whether a field of type `ty` is filled with the capture
+    pub fn matches(self, ty: &Type) -> bool {
+        let ty = match (self, ty) {
+            (Self::Location, Type::Reference(reference)) => &*reference.elem,
+            (Self::Backtrace, ty) => ty,
+            _ => return false,
+        };
+        // Imports cannot be resolved, so only the full path identifies the type,
+        // rather than any type with the same name
+        let paths: &[&[&str]] = match self {
+            Self::Location => &[
+                &["core", "panic", "Location"],
+                &["std", "panic", "Location"],
+            ],
+            Self::Backtrace => &[&["std", "backtrace", "Backtrace"]],
+        };
+
+        let Type::Path(type_path) = ty else {
+            return false;
+        };
+        type_path.qself.is_none()
+            && paths.iter().any(|path| {
+                type_path.path.segments.len() == path.len()
+                    && type_path
+                        .path
+                        .segments
+                        .iter()
+                        .zip(*path)
+                        .all(|(segment, ident)| segment.ident == ident)
+            })
+    }
+
+    pub fn expr(self, span: Span) -> Expr {
+        match self {
+            Self::Location => parse_quote_spanned!(span => ::core::panic::Location::caller()),
+            Self::Backtrace => {
+                parse_quote_spanned!(span => ::std::backtrace::Backtrace::capture())
+            }
+        }
+    }
+}
+
+impl Parse for Capture {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(CaptureParse::parse(input)?.capture)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CaptureParse {
+    capture: Capture,
+    span: Span,
+}
+
+impl CaptureParse {
+    pub fn capture(&self) -> Capture {
+        self.capture
+    }
+}
+
+impl Parse for CaptureParse {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+
+        let ident: Ident = input.parse()?;
+        let capture = match ident.to_string().as_str() {
+            "location" => Ok(Capture::Location),
+            "backtrace" => Ok(Capture::Backtrace),
+            _ => Err(syn::Error::new_spanned(
+                ident,
+                "Invalid capture (expected 'location' or 'backtrace')",
+            )),
+        }?;
+
+        Ok(Self { capture, span })
+    }
+}
+
+impl ToTokens for CaptureParse {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let s = match self.capture {
+            Capture::Location => "location",
+            Capture::Backtrace => "backtrace",
+        };
+        let ident = Ident::new(s, self.span);
+        ident.to_tokens(tokens);
+    }
+}
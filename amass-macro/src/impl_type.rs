@@ -8,6 +8,9 @@ use syn::{
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) enum ImplType {
     From,
+    TryFrom,
+    AsLeaf,
+    Leaf,
 }
 
 impl Parse for ImplType {
@@ -22,6 +25,20 @@ pub(crate) struct ImplTypeParse {
     span: Span,
 }
 
+impl ImplTypeParse {
+    pub fn new(impl_type: ImplType, span: Span) -> Self {
+        Self { impl_type, span }
+    }
+
+    pub fn impl_type(&self) -> ImplType {
+        self.impl_type
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
 impl From<ImplTypeParse> for ImplType {
     fn from(value: ImplTypeParse) -> Self {
         value.impl_type
@@ -35,6 +52,9 @@ impl Parse for ImplTypeParse {
         let ident: Ident = input.parse()?;
         let impl_type = match ident.to_string().as_str() {
             "From" => Ok(ImplType::From),
+            "TryFrom" => Ok(ImplType::TryFrom),
+            "AsLeaf" => Ok(ImplType::AsLeaf),
+            "Leaf" => Ok(ImplType::Leaf),
             _ => Err(syn::Error::new_spanned(ident, "Cannot impl this type")),
         }?;
 
@@ -46,6 +66,9 @@ impl ToTokens for ImplTypeParse {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let s = match self.impl_type {
             ImplType::From => "From",
+            ImplType::TryFrom => "TryFrom",
+            ImplType::AsLeaf => "AsLeaf",
+            ImplType::Leaf => "Leaf",
         };
         let ident = Ident::new(s, self.span);
         ident.to_tokens(tokens);
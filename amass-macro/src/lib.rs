@@ -1,9 +1,13 @@
+mod capture;
 mod impl_type;
+mod on_conflict;
+mod on_cycle;
 mod options;
 mod parse;
 mod proc_impl;
 mod syn_util;
 mod variant_action;
+mod wrapper;
 
 use proc_macro::TokenStream;
 
@@ -36,3 +40,19 @@ pub fn amass_apply(attr: TokenStream) -> TokenStream {
         proc_impl::amass_apply(attr.into()).map_err(syn::Error::into_compile_error);
     ts.into()
 }
+
+#[proc_macro]
+#[doc(hidden)]
+pub fn amass_delegate(attr: TokenStream) -> TokenStream {
+    let (Ok(ts) | Err(ts)) =
+        proc_impl::amass_delegate(attr.into()).map_err(syn::Error::into_compile_error);
+    ts.into()
+}
+
+#[proc_macro]
+#[doc(hidden)]
+pub fn amass_generated(attr: TokenStream) -> TokenStream {
+    let (Ok(ts) | Err(ts)) =
+        proc_impl::amass_generated(attr.into()).map_err(syn::Error::into_compile_error);
+    ts.into()
+}
@@ -0,0 +1,66 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{
+    Ident,
+    parse::{Parse, ParseStream},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnConflict {
+    First,
+    Prefer,
+    Error,
+}
+
+impl Parse for OnConflict {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(OnConflictParse::parse(input)?.on_conflict)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct OnConflictParse {
+    on_conflict: OnConflict,
+    span: Span,
+}
+
+impl OnConflictParse {
+    pub fn new(on_conflict: OnConflict, span: Span) -> Self {
+        Self { on_conflict, span }
+    }
+
+    pub fn on_conflict(&self) -> OnConflict {
+        self.on_conflict
+    }
+}
+
+impl Parse for OnConflictParse {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+
+        let ident: Ident = input.parse()?;
+        let on_conflict = match ident.to_string().as_str() {
+            "first" => Ok(OnConflict::First),
+            "prefer" => Ok(OnConflict::Prefer),
+            "error" => Ok(OnConflict::Error),
+            _ => Err(syn::Error::new_spanned(
+                ident,
+                "Invalid conflict resolution (expected 'first', 'prefer', or 'error')",
+            )),
+        }?;
+
+        Ok(Self { on_conflict, span })
+    }
+}
+
+impl ToTokens for OnConflictParse {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let s = match self.on_conflict {
+            OnConflict::First => "first",
+            OnConflict::Prefer => "prefer",
+            OnConflict::Error => "error",
+        };
+        let ident = Ident::new(s, self.span);
+        ident.to_tokens(tokens);
+    }
+}
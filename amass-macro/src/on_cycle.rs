@@ -0,0 +1,59 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{
+    Ident,
+    parse::{Parse, ParseStream},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnCycle {
+    Ignore,
+    Error,
+}
+
+impl Parse for OnCycle {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(OnCycleParse::parse(input)?.on_cycle)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct OnCycleParse {
+    on_cycle: OnCycle,
+    span: Span,
+}
+
+impl OnCycleParse {
+    pub fn on_cycle(&self) -> OnCycle {
+        self.on_cycle
+    }
+}
+
+impl Parse for OnCycleParse {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+
+        let ident: Ident = input.parse()?;
+        let on_cycle = match ident.to_string().as_str() {
+            "ignore" => Ok(OnCycle::Ignore),
+            "error" => Ok(OnCycle::Error),
+            _ => Err(syn::Error::new_spanned(
+                ident,
+                "Invalid cycle handling (expected 'ignore' or 'error')",
+            )),
+        }?;
+
+        Ok(Self { on_cycle, span })
+    }
+}
+
+impl ToTokens for OnCycleParse {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let s = match self.on_cycle {
+            OnCycle::Ignore => "ignore",
+            OnCycle::Error => "error",
+        };
+        let ident = Ident::new(s, self.span);
+        ident.to_tokens(tokens);
+    }
+}
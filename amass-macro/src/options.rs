@@ -1,18 +1,38 @@
-use proc_macro2::TokenStream;
-use quote::ToTokens;
+use proc_macro2::{Delimiter, Group, Span, TokenStream};
+use quote::{ToTokens, quote};
 use syn::{
-    Attribute, Ident, MetaNameValue, Token,
+    Attribute, Ident, LitInt, MacroDelimiter, Meta, MetaList, Path, Token, parenthesized,
     parse::{Parse, ParseStream},
     parse2,
     punctuated::Punctuated,
     spanned::Spanned as _,
 };
 
-use crate::variant_action::VariantAction;
+use crate::{
+    capture::CaptureParse,
+    impl_type::{ImplType, ImplTypeParse},
+    on_conflict::OnConflictParse,
+    on_cycle::OnCycleParse,
+    syn_util,
+    variant_action::{ActionOverride, VariantAction},
+};
 
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub(crate) struct Options {
     pub default: Option<VariantAction>,
+    pub impls: Option<Punctuated<ImplTypeParse, Token![,]>>,
+    pub overrides: Option<Punctuated<ActionOverride, Token![,]>>,
+    pub on_conflict: Option<OnConflictParse>,
+    pub on_cycle: Option<OnCycleParse>,
+    pub capture: Option<CaptureParse>,
+    pub max_depth: Option<LitInt>,
+    pub kind: Option<Ident>,
+    pub flat: Option<Ident>,
+    pub conversions: Option<Ident>,
+    pub delegate: Option<Punctuated<Path, Token![,]>>,
+    pub error: bool,
+    pub explain: Option<Ident>,
+    pub path: bool,
 }
 
 impl Options {
@@ -27,19 +47,18 @@ impl Options {
                 .ident
                 == "amass"
             {
-                if let Some(options) = match &attr.meta {
-                    syn::Meta::Path(_) => Some(Options::default()),
-                    syn::Meta::List(meta_list) => Some(parse2(meta_list.tokens.clone())?),
+                let options = match &attr.meta {
+                    syn::Meta::Path(_) => Options::default(),
+                    syn::Meta::List(meta_list) => parse2(meta_list.tokens.clone())?,
                     syn::Meta::NameValue(_) => {
                         return Err(syn::Error::new(attr.meta.span(), "expected list arguments"));
                     }
-                } {
-                    if args.replace(options).is_some() {
-                        return Err(syn::Error::new(
-                            attr.span(),
-                            "Only one 'amass' attribute is allowed",
-                        ));
-                    }
+                };
+                if args.replace(options).is_some() {
+                    return Err(syn::Error::new(
+                        attr.span(),
+                        "Only one 'amass' attribute is allowed",
+                    ));
                 }
             }
         }
@@ -51,6 +70,14 @@ impl Options {
             )
         })
     }
+
+    pub fn impls_or_default(&self, span: Span) -> Punctuated<ImplTypeParse, Token![,]> {
+        self.impls.clone().unwrap_or_else(|| {
+            let mut impls = Punctuated::new();
+            impls.push(ImplTypeParse::new(ImplType::From, span));
+            impls
+        })
+    }
 }
 
 impl Parse for Options {
@@ -58,52 +85,254 @@ impl Parse for Options {
         let options: OptionsParse = input.parse()?;
 
         let mut default = None;
+        let mut impls = None;
+        let mut overrides = None;
+        let mut on_conflict = None;
+        let mut on_cycle = None;
+        let mut capture = None;
+        let mut max_depth = None;
+        let mut kind = None;
+        let mut flat = None;
+        let mut conversions = None;
+        let mut delegate = None;
+        let mut error = None;
+        let mut explain = None;
+        let mut path = None;
 
-        if let Some((_, named_args)) = &options.args {
-            for named_arg in named_args {
-                match named_arg.path.get_ident().map(Ident::to_string).as_deref() {
-                    Some("default") => {
-                        if default
-                            .replace(parse2(named_arg.value.to_token_stream())?)
-                            .is_some()
+        for arg in &options.args {
+            match arg.path().get_ident().map(Ident::to_string).as_deref() {
+                Some("default") => {
+                    let value = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    syn_util::set_once(&mut default, value, arg.span())?;
+                }
+                Some("impls") => {
+                    let list: Punctuated<ImplTypeParse, Token![,]> = arg
+                        .require_list()?
+                        .parse_args_with(Punctuated::parse_terminated)?;
+                    for (index, impl_type) in list.iter().enumerate() {
+                        if list
+                            .iter()
+                            .take(index)
+                            .any(|i| i.impl_type() == impl_type.impl_type())
                         {
                             return Err(syn::Error::new(
-                                named_arg.span(),
-                                "parameter appears multiple times",
+                                impl_type.span(),
+                                "impl appears multiple times",
                             ));
                         }
                     }
-                    _ => return Err(syn::Error::new(named_arg.path.span(), "Invalid parameter")),
+                    syn_util::set_once(&mut impls, list, arg.span())?;
+                }
+                Some("override") => {
+                    let list = arg
+                        .require_list()?
+                        .parse_args_with(Punctuated::parse_terminated)?;
+                    syn_util::set_once(&mut overrides, list, arg.span())?;
+                }
+                Some("on_conflict") => {
+                    let value = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    syn_util::set_once(&mut on_conflict, value, arg.span())?;
+                }
+                Some("on_cycle") => {
+                    let value = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    syn_util::set_once(&mut on_cycle, value, arg.span())?;
+                }
+                Some("capture") => {
+                    let value = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    syn_util::set_once(&mut capture, value, arg.span())?;
+                }
+                Some("max_depth") => {
+                    let value: LitInt = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    if value.base10_parse::<usize>()? == 0 {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "max_depth must be at least 1",
+                        ));
+                    }
+                    syn_util::set_once(&mut max_depth, value, arg.span())?;
+                }
+                Some("kind") => {
+                    let value = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    syn_util::set_once(&mut kind, value, arg.span())?;
+                }
+                Some("flat") => {
+                    let value = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    syn_util::set_once(&mut flat, value, arg.span())?;
+                }
+                Some("conversions") => {
+                    let value = parse2(arg.require_name_value()?.value.to_token_stream())?;
+                    syn_util::set_once(&mut conversions, value, arg.span())?;
+                }
+                Some("delegate") => {
+                    let list = arg
+                        .require_list()?
+                        .parse_args_with(Punctuated::parse_terminated)?;
+                    syn_util::set_once(&mut delegate, list, arg.span())?;
+                }
+                Some("error") => {
+                    arg.require_path_only()?;
+                    syn_util::set_once(&mut error, (), arg.span())?;
+                }
+                Some("explain") => {
+                    arg.require_path_only()?;
+                    let ident = arg.path().require_ident()?.clone();
+                    syn_util::set_once(&mut explain, ident, arg.span())?;
+                }
+                Some("path") => {
+                    arg.require_path_only()?;
+                    syn_util::set_once(&mut path, (), arg.span())?;
                 }
+                _ => return Err(syn::Error::new(arg.path().span(), "Invalid parameter")),
             }
         }
 
-        Ok(Self { default })
+        Ok(Self {
+            default,
+            impls,
+            overrides,
+            on_conflict,
+            on_cycle,
+            capture,
+            max_depth,
+            kind,
+            flat,
+            conversions,
+            delegate,
+            error: error.is_some(),
+            explain,
+            path: path.is_some(),
+        })
+    }
+}
+
+/// The options of the root which apply to the whole tree.
+/// Forwarded to nested expansions in the same syntax as the attribute, e.g. `(impls(From), on_conflict = error)`
+#[derive(Clone)]
+pub(crate) struct RootOptions {
+    pub impls: Punctuated<ImplTypeParse, Token![,]>,
+    pub overrides: Punctuated<ActionOverride, Token![,]>,
+    pub on_conflict: Option<OnConflictParse>,
+    pub on_cycle: Option<OnCycleParse>,
+    pub capture: Option<CaptureParse>,
+    pub max_depth: Option<LitInt>,
+    pub kind: Option<Ident>,
+    pub flat: Option<Ident>,
+    pub conversions: Option<Ident>,
+    pub explain: Option<Ident>,
+}
+
+impl Parse for RootOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        let Options {
+            impls,
+            overrides,
+            on_conflict,
+            on_cycle,
+            capture,
+            max_depth,
+            kind,
+            flat,
+            conversions,
+            explain,
+            ..
+        } = content.parse()?;
+
+        Ok(Self {
+            impls: impls.unwrap_or_default(),
+            overrides: overrides.unwrap_or_default(),
+            on_conflict,
+            on_cycle,
+            capture,
+            max_depth,
+            kind,
+            flat,
+            conversions,
+            explain,
+        })
+    }
+}
+
+impl ToTokens for RootOptions {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            impls,
+            overrides,
+            on_conflict,
+            on_cycle,
+            capture,
+            max_depth,
+            kind,
+            flat,
+            conversions,
+            explain,
+        } = self;
+        let on_conflict = on_conflict.iter();
+        let on_cycle = on_cycle.iter();
+        let capture = capture.iter();
+        let max_depth = max_depth.iter();
+        let kind = kind.iter();
+        let flat = flat.iter();
+        let conversions = conversions.iter();
+        quote! {(
+            impls(#impls),
+            override(#overrides),
+            #(on_conflict = #on_conflict,)*
+            #(on_cycle = #on_cycle,)*
+            #(capture = #capture,)*
+            #(max_depth = #max_depth,)*
+            #(kind = #kind,)*
+            #(flat = #flat,)*
+            #(conversions = #conversions,)*
+            #explain
+        )}
+        .to_tokens(tokens);
     }
 }
 
 pub(crate) struct OptionsParse {
-    pub args: Option<(Token![,], Punctuated<MetaNameValue, Token![,]>)>,
+    pub leading_comma: Option<Token![,]>,
+    pub args: Punctuated<Meta, Token![,]>,
 }
 
 impl Parse for OptionsParse {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let args = if let Some(comma) = input.parse::<Option<Token![,]>>()? {
-            let named_args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated(input)?;
-            Some((comma, named_args))
-        } else {
-            None
-        };
+        // `#[amass_telety(...)]` forwards its arguments with the leading comma
+        let leading_comma = input.parse()?;
+        let args = Punctuated::<Meta, Token![,]>::parse_terminated_with(input, Self::parse_arg)?;
 
-        Ok(Self { args })
+        Ok(Self {
+            leading_comma,
+            args,
+        })
+    }
+}
+
+impl OptionsParse {
+    // `override` is a reserved keyword, which `Meta` will not accept as a path
+    fn parse_arg(input: ParseStream) -> syn::Result<Meta> {
+        if input.peek(Token![override]) {
+            let keyword: Token![override] = input.parse()?;
+            let group: Group = input.parse()?;
+            if group.delimiter() != Delimiter::Parenthesis {
+                return Err(syn::Error::new(group.span(), "expected parentheses"));
+            }
+
+            Ok(Meta::List(MetaList {
+                path: Path::from(Ident::new("override", keyword.span)),
+                delimiter: MacroDelimiter::Paren(syn::token::Paren(group.delim_span())),
+                tokens: group.stream(),
+            }))
+        } else {
+            input.parse()
+        }
     }
 }
 
 impl ToTokens for OptionsParse {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        if let Some((comma, args)) = &self.args {
-            comma.to_tokens(tokens);
-            args.to_tokens(tokens);
-        }
+        self.leading_comma.to_tokens(tokens);
+        self.args.to_tokens(tokens);
     }
 }
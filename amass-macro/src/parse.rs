@@ -1,17 +1,36 @@
+use proc_macro2::Span;
+use std::ops::Range;
+
 use proc_macro2::TokenStream;
-use quote::{ToTokens, quote_spanned};
+use quote::{ToTokens, format_ident, quote, quote_spanned};
 use syn::{
-    AngleBracketedGenericArguments, Field, Generics, Ident, Item, Path, PathArguments, PathSegment,
-    Token, Type, Variant, bracketed,
+    AngleBracketedGenericArguments, Attribute, Field, FieldValue, Generics, Ident, Index, Item,
+    LitStr, Member, Path, PathArguments, PathSegment, Token, Type, Variant, braced, bracketed,
+    parenthesized,
     parse::{Parse, ParseStream},
+    parse_quote,
     punctuated::Punctuated,
     spanned::Spanned as _,
-    token::Bracket,
+    token::{Brace, Bracket, Paren},
+};
+
+use crate::{
+    capture::{Capture, CaptureParse},
+    impl_type::ImplType,
+    on_conflict::{OnConflict, OnConflictParse},
+    on_cycle::{OnCycle, OnCycleParse},
+    options::RootOptions,
+    proc_impl::AmassTraversal,
+    syn_util,
+    variant_action::{ActionOverride, VariantAction},
+    wrapper::{Wrapper, WrapperParse},
 };
 
 pub(crate) struct AmassFrom {
     pub common: AmassCommon,
-    pub leaf_type: syn::TypePath,
+    pub leaf_type: Type,
+    /// Only present if the root should `explain` its conversions
+    pub leaf_name: Option<LitStr>,
 }
 
 impl Parse for AmassFrom {
@@ -19,30 +38,142 @@ impl Parse for AmassFrom {
         Ok(Self {
             common: input.parse()?,
             leaf_type: input.parse()?,
+            leaf_name: input.parse()?,
         })
     }
 }
 
 impl ToTokens for AmassFrom {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let Self { common, leaf_type } = self;
+        let Self {
+            common,
+            leaf_type,
+            leaf_name,
+        } = self;
         common.to_tokens(tokens);
         leaf_type.to_tokens(tokens);
+        leaf_name.to_tokens(tokens);
     }
 }
 
+/// A single-field variant, or the field of a single-field struct
+#[derive(Clone)]
 pub(crate) struct AmassVariant {
+    // `cfg` and `deprecated` attributes of the variant
+    attrs: Vec<Attribute>,
+    struct_token: Option<Token![struct]>,
+    item_ident: Ident,
+    colon: Token![:],
     path: Path,
-    named_field: Option<(Token![.], Ident)>,
+    field: Option<(Token![.], AmassMember)>,
+    // The other fields of the variant and the expressions which fill them
+    fills: Option<(Brace, Punctuated<FieldValue, Token![,]>)>,
+    function: Option<AmassFunction>,
+    wrapper: Option<AmassWrapper>,
+}
+
+/// The field(s) of a variant which hold the leaf
+#[derive(Clone)]
+enum AmassMember {
+    Single(Member),
+    /// All fields of a variant, converted from a tuple
+    Tuple(Paren, Punctuated<Member, Token![,]>),
+}
+
+impl AmassMember {
+    fn tuple_bindings(len: usize, span: Span) -> Vec<Ident> {
+        (0..len)
+            .map(|index| format_ident!("value_{index}", span = span))
+            .collect()
+    }
+}
+
+impl Parse for AmassMember {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Paren) {
+            let members_content;
+            Ok(Self::Tuple(
+                parenthesized!(members_content in input),
+                Punctuated::parse_terminated(&members_content)?,
+            ))
+        } else {
+            Ok(Self::Single(input.parse()?))
+        }
+    }
+}
+
+impl ToTokens for AmassMember {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Single(member) => member.to_tokens(tokens),
+            Self::Tuple(paren, members) => paren.surround(tokens, |ts| members.to_tokens(ts)),
+        }
+    }
 }
 
 impl AmassVariant {
-    pub fn from_field(enum_type: &Path, variant: &Variant, field: &Field) -> Option<Self> {
-        let named_field = field
-            .ident
-            .as_ref()
-            .map(|i| (Default::default(), i.clone()));
+    pub fn from_field(
+        enum_type: &Path,
+        enum_ident: &Ident,
+        variant: &Variant,
+        field: &Field,
+    ) -> Option<Self> {
+        let field = field.ident.as_ref().map(|i| {
+            (
+                Default::default(),
+                AmassMember::Single(Member::Named(i.clone())),
+            )
+        });
+
+        let mut path = enum_type.clone();
+        for segment in path.segments.iter_mut() {
+            segment.arguments = PathArguments::None;
+        }
+        path.segments.push(PathSegment {
+            ident: variant.ident.clone(),
+            arguments: PathArguments::None,
+        });
+
+        Some(Self {
+            attrs: inherited_attrs(&variant.attrs),
+            struct_token: None,
+            item_ident: enum_ident.clone(),
+            colon: Default::default(),
+            path,
+            field,
+            fills: None,
+            function: None,
+            wrapper: None,
+        })
+    }
+
+    pub fn from_struct_field(struct_type: &Path, struct_ident: &Ident, field: &Field) -> Self {
+        // Braced syntax also works for tuple structs, and through type aliases
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(0)),
+        };
 
+        let mut path = struct_type.clone();
+        for segment in path.segments.iter_mut() {
+            segment.arguments = PathArguments::None;
+        }
+
+        Self {
+            attrs: inherited_attrs(&field.attrs),
+            struct_token: Some(Default::default()),
+            item_ident: struct_ident.clone(),
+            colon: Default::default(),
+            path,
+            field: Some((Default::default(), AmassMember::Single(member))),
+            fills: None,
+            function: None,
+            wrapper: None,
+        }
+    }
+
+    /// A variant which is only matched, not converted
+    pub fn from_variant(enum_type: &Path, enum_ident: &Ident, variant: &Variant) -> Self {
         let mut path = enum_type.clone();
         for segment in path.segments.iter_mut() {
             segment.arguments = PathArguments::None;
@@ -52,37 +183,441 @@ impl AmassVariant {
             arguments: PathArguments::None,
         });
 
-        Some(Self { path, named_field })
+        Self {
+            attrs: inherited_attrs(&variant.attrs),
+            struct_token: None,
+            item_ident: enum_ident.clone(),
+            colon: Default::default(),
+            path,
+            field: None,
+            fills: None,
+            function: None,
+            wrapper: None,
+        }
+    }
+
+    /// A variant whose fields are converted from a tuple
+    pub fn from_tuple_fields(enum_type: &Path, enum_ident: &Ident, variant: &Variant) -> Self {
+        // Braced syntax also works for tuple variants
+        let members = variant
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index::from(index)),
+            })
+            .collect();
+
+        let mut path = enum_type.clone();
+        for segment in path.segments.iter_mut() {
+            segment.arguments = PathArguments::None;
+        }
+        path.segments.push(PathSegment {
+            ident: variant.ident.clone(),
+            arguments: PathArguments::None,
+        });
+
+        Self {
+            attrs: inherited_attrs(&variant.attrs),
+            struct_token: None,
+            item_ident: enum_ident.clone(),
+            colon: Default::default(),
+            path,
+            field: Some((
+                Default::default(),
+                AmassMember::Tuple(Default::default(), members),
+            )),
+            fills: None,
+            function: None,
+            wrapper: None,
+        }
+    }
+
+    /// The variant has other fields, which are filled by `fills` when converting.
+    /// `index` is the position of the converted field, for unnamed fields.
+    pub fn with_fills(mut self, index: usize, fills: Punctuated<FieldValue, Token![,]>) -> Self {
+        if self.field.is_none() {
+            self.field = Some((
+                Default::default(),
+                AmassMember::Single(Member::Unnamed(Index::from(index))),
+            ));
+        }
+        self.fills = Some((Default::default(), fills));
+        self
+    }
+
+    /// The field is built by calling `path` with the leaf
+    pub fn with_function(mut self, path: Path) -> Self {
+        self.function = Some(AmassFunction {
+            fn_token: Default::default(),
+            paren: Default::default(),
+            path,
+        });
+        self
+    }
+
+    /// The field is a wrapper (e.g. `Box<T>`) which can be accessed through `path`.
+    /// If `through`, the wrapper is around the next value, otherwise the wrapper is the leaf.
+    pub fn with_wrapper(mut self, wrapper: Wrapper, path: Path, through: bool) -> Self {
+        let span = path.span();
+        self.wrapper = Some(AmassWrapper {
+            through,
+            wrapper: WrapperParse::new(wrapper, span),
+            paren: Default::default(),
+            path,
+        });
+        self
+    }
+
+    /// The variant as written by the user, e.g. `Enum::Variant`, or `Struct` for a struct
+    pub fn describe(&self) -> String {
+        if self.struct_token.is_some() {
+            return self.item_ident.to_string();
+        }
+
+        let variant = &self
+            .path
+            .segments
+            .last()
+            .expect("variant path must not be empty")
+            .ident;
+        format!("{}::{variant}", self.item_ident)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn span(&self) -> Span {
+        if self.struct_token.is_some() {
+            return self.item_ident.span();
+        }
+
+        self.path
+            .segments
+            .last()
+            .expect("variant path must not be empty")
+            .ident
+            .span()
+    }
+
+    /// Matches the variant, binding the field to `inner` if provided
+    pub fn kind_pattern(&self, inner: Option<TokenStream>) -> TokenStream {
+        let path = &self.path;
+        match (inner, &self.field) {
+            (Some(inner), Some((_dot, AmassMember::Single(field)))) => {
+                quote!(#path { #field: #inner, .. })
+            }
+            (Some(inner), None) => quote!(#path(#inner)),
+            _ => quote!(#path { .. }),
+        }
+    }
+
+    fn tuple_members(&self) -> Option<&Punctuated<Member, Token![,]>> {
+        match &self.field {
+            Some((_dot, AmassMember::Tuple(_paren, members))) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// The wrapper around the next value, if any
+    pub fn through(&self) -> Option<Wrapper> {
+        self.through_wrapper().map(|w| w.wrapper.wrapper())
+    }
+
+    pub fn has_fills(&self) -> bool {
+        self.fills.is_some()
+    }
+
+    /// Whether the field is built by a function, which cannot be reversed
+    pub fn has_function(&self) -> bool {
+        self.function.is_some()
+    }
+
+    /// Whether this is the variant named `ident` of its enum
+    pub fn is_variant(&self, ident: &Ident) -> bool {
+        self.struct_token.is_none() && self.path.segments.last().is_some_and(|s| s.ident == *ident)
+    }
+
+    /// Whether the leaf is the wrapper itself, rather than the value inside it
+    pub fn is_wrapper_leaf(&self) -> bool {
+        self.wrapper.as_ref().is_some_and(|w| !w.through)
+    }
+
+    pub fn cfg_attrs(&self) -> impl Iterator<Item = &Attribute> {
+        self.attrs.iter().filter(|attr| attr.path().is_ident("cfg"))
+    }
+
+    pub fn deprecated(&self) -> Option<&Attribute> {
+        self.attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("deprecated"))
+    }
+
+    /// Uses of the variant are reported at `span`
+    fn respan_path(&mut self, span: Span) {
+        let path = syn_util::respan(self.path.to_token_stream(), span);
+        self.path = parse_quote!(#path);
+    }
+
+    fn through_wrapper(&self) -> Option<&AmassWrapper> {
+        self.wrapper.as_ref().filter(|w| w.through)
+    }
+
+    /// The alias path of the enum or struct containing this variant
+    fn item_path(&self) -> Path {
+        let mut path = self.path.clone();
+        if self.struct_token.is_none() {
+            path.segments.pop();
+            path.segments.pop_punct();
+        }
+        path
+    }
+
+    pub fn is_same(&self, other: &Self) -> bool {
+        self.to_token_stream().to_string() == other.to_token_stream().to_string()
+    }
+}
+
+/// `cfg` and `deprecated` apply to everything generated for a variant
+pub(crate) fn inherited_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg") || attr.path().is_ident("deprecated"))
+        .cloned()
+        .collect()
+}
+
+/// All `cfg` attributes along a chain of variants
+pub(crate) fn cfg_attrs<'a>(
+    variants: impl IntoIterator<Item = &'a AmassVariant>,
+) -> impl Iterator<Item = &'a Attribute> {
+    variants.into_iter().flat_map(AmassVariant::cfg_attrs)
+}
+
+/// Whether any variant is behind a `cfg`, which can't be evaluated here.
+/// Duplicate leaves are allowed through these, as the variants may never be compiled together.
+pub(crate) fn is_conditional<'a>(variants: impl IntoIterator<Item = &'a AmassVariant>) -> bool {
+    cfg_attrs(variants).next().is_some()
+}
+
+/// The exported macro which declares a generated enum.
+/// It must be unique within the crate, so is named after the root's unique ident.
+pub(crate) fn generated_macro(unique_ident: &Ident, ident: &Ident) -> Ident {
+    format_ident!("__amass_{unique_ident}_{ident}")
+}
+
+/// Describes a chain of variants, e.g. `Root::Variant -> Nested::Variant`
+pub(crate) fn describe_variants(variants: &Punctuated<AmassVariant, Token![,]>) -> String {
+    variants
+        .iter()
+        .map(AmassVariant::describe)
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Reports a leaf which is converted through two chains of variants, which would otherwise be conflicting impls.
+/// The error points at the variants where the chains diverge, as one of them should be `ignore` or `shallow`.
+pub(crate) fn duplicate_error(
+    leaf: &Type,
+    a: &Punctuated<AmassVariant, Token![,]>,
+    b: &Punctuated<AmassVariant, Token![,]>,
+) -> syn::Error {
+    let diverging = a
+        .iter()
+        .zip(b)
+        .position(|(a, b)| !a.is_same(b))
+        .unwrap_or_default();
+    let span = |variants: &Punctuated<AmassVariant, Token![,]>| {
+        variants
+            .get(diverging)
+            .map_or_else(Span::call_site, AmassVariant::span)
+    };
+
+    let leaf = describe_type(leaf);
+    let mut error = syn::Error::new(
+        span(b),
+        format!(
+            "`{leaf}` is converted through both `{}` and `{}` \
+            (use `#[amass_action(ignore)]` or `#[amass_action(shallow)]` on one of them)",
+            describe_variants(a),
+            describe_variants(b),
+        ),
+    );
+    error.combine(syn::Error::new(
+        span(a),
+        format!(
+            "`{leaf}` is also converted through `{}`",
+            describe_variants(a)
+        ),
+    ));
+    error
+}
+
+/// The name of a type as written, without its path
+pub(crate) fn describe_type(ty: &Type) -> String {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default(),
+        ty => ty.to_token_stream().to_string(),
     }
 }
 
 impl Parse for AmassVariant {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let struct_token = input.parse()?;
+        let item_ident = input.parse()?;
+        let colon = input.parse()?;
         let path = input.parse()?;
         let field = if let Some(dot) = input.parse()? {
             Some((dot, input.parse()?))
         } else {
             None
         };
+        let fills = if input.peek(Brace) {
+            let fills_content;
+            Some((
+                braced!(fills_content in input),
+                Punctuated::parse_terminated(&fills_content)?,
+            ))
+        } else {
+            None
+        };
+        let function = if input.peek(Token![fn]) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let wrapper = if input.peek(Token![in]) || input.peek(Token![as]) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
 
         Ok(Self {
+            attrs,
+            struct_token,
+            item_ident,
+            colon,
             path,
-            named_field: field,
+            field,
+            fills,
+            function,
+            wrapper,
         })
     }
 }
 
 impl ToTokens for AmassVariant {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        for attr in &self.attrs {
+            attr.to_tokens(tokens);
+        }
+        self.struct_token.to_tokens(tokens);
+        self.item_ident.to_tokens(tokens);
+        self.colon.to_tokens(tokens);
         self.path.to_tokens(tokens);
-        if let Some((dot, field)) = &self.named_field {
+        if let Some((dot, field)) = &self.field {
             dot.to_tokens(tokens);
             field.to_tokens(tokens);
         }
+        if let Some((brace, fills)) = &self.fills {
+            brace.surround(tokens, |ts| fills.to_tokens(ts));
+        }
+        self.function.to_tokens(tokens);
+        self.wrapper.to_tokens(tokens);
     }
 }
 
+#[derive(Clone)]
+struct AmassFunction {
+    fn_token: Token![fn],
+    paren: Paren,
+    // (
+    path: Path,
+    // )
+}
+
+impl Parse for AmassFunction {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path_content;
+        Ok(Self {
+            fn_token: input.parse()?,
+            paren: parenthesized!(path_content in input),
+            path: path_content.parse()?,
+        })
+    }
+}
+
+impl ToTokens for AmassFunction {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            fn_token,
+            paren,
+            path,
+        } = self;
+        fn_token.to_tokens(tokens);
+        paren.surround(tokens, |ts| path.to_tokens(ts));
+    }
+}
+
+#[derive(Clone)]
+struct AmassWrapper {
+    // `in` if the wrapper is converted through, `as` if it is the leaf
+    through: bool,
+    wrapper: WrapperParse,
+    paren: Paren,
+    // (
+    path: Path,
+    // )
+}
+
+impl Parse for AmassWrapper {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path_content;
+        let through = if input.peek(Token![in]) {
+            input.parse::<Token![in]>()?;
+            true
+        } else {
+            input.parse::<Token![as]>()?;
+            false
+        };
+        Ok(Self {
+            through,
+            wrapper: input.parse()?,
+            paren: parenthesized!(path_content in input),
+            path: path_content.parse()?,
+        })
+    }
+}
+
+impl ToTokens for AmassWrapper {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            through,
+            wrapper,
+            paren,
+            path,
+        } = self;
+        if *through {
+            <Token![in]>::default().to_tokens(tokens);
+        } else {
+            <Token![as]>::default().to_tokens(tokens);
+        }
+        wrapper.to_tokens(tokens);
+        paren.surround(tokens, |ts| path.to_tokens(ts));
+    }
+}
+
+#[derive(Clone)]
 pub(crate) struct AmassCommon {
+    options: RootOptions,
     parameters: Generics,
     root_type: Type,
     variants_bracket: Bracket,
@@ -92,8 +627,9 @@ pub(crate) struct AmassCommon {
 }
 
 impl AmassCommon {
-    pub fn new(parameters: Generics, root_type: Type) -> Self {
+    pub fn new(options: RootOptions, parameters: Generics, root_type: Type) -> Self {
         Self {
+            options,
             parameters,
             root_type,
             variants_bracket: Default::default(),
@@ -108,16 +644,124 @@ impl AmassCommon {
     pub fn pop_variant(&mut self) {
         self.variants.pop().expect("unbalanced variant stack");
     }
+
+    pub fn options(&self) -> &RootOptions {
+        &self.options
+    }
+
+    pub fn parameters(&self) -> &Generics {
+        &self.parameters
+    }
+
+    pub fn root_type(&self) -> &Type {
+        &self.root_type
+    }
+
+    pub fn variants(&self) -> &Punctuated<AmassVariant, Token![,]> {
+        &self.variants
+    }
+
+    pub fn set_variants(&mut self, variants: Punctuated<AmassVariant, Token![,]>) {
+        self.variants = variants;
+    }
+
+    pub fn on_conflict(&self) -> Option<OnConflict> {
+        self.options
+            .on_conflict
+            .as_ref()
+            .map(OnConflictParse::on_conflict)
+    }
+
+    pub fn on_cycle(&self) -> OnCycle {
+        self.options
+            .on_cycle
+            .as_ref()
+            .map_or(OnCycle::Ignore, OnCycleParse::on_cycle)
+    }
+
+    /// The leaf as written, if the conversions should be explained
+    pub fn leaf_name(&self, key: &Type) -> Option<LitStr> {
+        self.options
+            .explain
+            .as_ref()
+            .map(|explain| LitStr::new(&syn_util::display_type(key), explain.span()))
+    }
+
+    pub fn capture(&self) -> Option<Capture> {
+        self.options.capture.as_ref().map(CaptureParse::capture)
+    }
+
+    /// The number of nested variants after which types are no longer reflected
+    pub fn max_depth(&self) -> Option<usize> {
+        self.options.max_depth.as_ref().map(|max_depth| {
+            max_depth
+                .base10_parse()
+                .expect("max_depth must be validated")
+        })
+    }
+
+    /// The path of variants to the current type, e.g. `Root::Variant -> Nested::Variant`
+    pub fn describe_variants(&self) -> String {
+        describe_variants(&self.variants)
+    }
+
+    /// Whether an item with the alias path `item_path` already contains the current type
+    pub fn contains_item(&self, item_path: &Path) -> bool {
+        let item_path = item_path.to_token_stream().to_string();
+        self.variants
+            .iter()
+            .any(|v| v.item_path().to_token_stream().to_string() == item_path)
+    }
+
+    /// Whether `ty` is a generic type parameter of the root
+    pub fn is_type_parameter(&self, ty: &Type) -> bool {
+        syn_util::is_type_parameter(&self.parameters, ty)
+    }
+
+    /// Conflict resolution and generated items require visiting the whole tree before generating any impls,
+    /// so nested enums are reflected one after another instead of independently.
+    pub fn is_sequential(&self) -> bool {
+        self.options.on_conflict.is_some()
+            || self.options.kind.is_some()
+            || self.options.flat.is_some()
+            || self.options.conversions.is_some()
+            || self.options.explain.is_some()
+    }
+
+    pub fn has_kind(&self) -> bool {
+        self.options.kind.is_some()
+    }
+
+    pub fn has_flat(&self) -> bool {
+        self.options.flat.is_some()
+    }
+
+    /// The root enum's override for a variant of `enum_ident`, if any.
+    /// Overrides naming the variant take precedence over overrides naming its field type.
+    pub fn action_override(&self, enum_ident: &Ident, variant: &Variant) -> Option<VariantAction> {
+        self.options
+            .overrides
+            .iter()
+            .find(|o| o.matches_variant(enum_ident, variant))
+            .or_else(|| {
+                self.options
+                    .overrides
+                    .iter()
+                    .find(|o| o.matches_type(variant))
+            })
+            .map(ActionOverride::action)
+    }
 }
 
 impl Parse for AmassCommon {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let content;
+        let variants_content;
         Ok(Self {
+            options: input.parse()?,
             parameters: input.parse()?,
             root_type: input.parse()?,
-            variants_bracket: bracketed!(content in input),
-            variants: Punctuated::parse_terminated(&content)?,
+            variants_bracket: bracketed!(variants_content in input),
+            variants: Punctuated::parse_terminated(&variants_content)?,
         })
     }
 }
@@ -125,11 +769,13 @@ impl Parse for AmassCommon {
 impl ToTokens for AmassCommon {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let Self {
+            options,
             parameters,
             root_type,
             variants_bracket,
             variants,
         } = self;
+        options.to_tokens(tokens);
         parameters.to_tokens(tokens);
         root_type.to_tokens(tokens);
         variants_bracket.surround(tokens, |ts| variants.to_tokens(ts));
@@ -138,52 +784,627 @@ impl ToTokens for AmassCommon {
 
 impl AmassFrom {
     pub fn generate(&self) -> TokenStream {
+        // `From` reports deprecated variants itself, so is kept apart from the other impls
+        let mut from = TokenStream::new();
+        let mut tokens = TokenStream::new();
+        for impl_type in self.impl_types() {
+            match impl_type {
+                ImplType::From => from = self.generate_from(),
+                ImplType::TryFrom => tokens.extend(self.generate_try_from()),
+                ImplType::AsLeaf => tokens.extend(self.generate_as_leaf()),
+                ImplType::Leaf => tokens.extend(self.generate_leaf()),
+            }
+        }
+
+        // The impls only exist where every variant along the path does.
+        // Deprecated variants are reported once by `From`, rather than by each use.
+        if self.common.variants.iter().any(|v| !v.attrs.is_empty()) {
+            let span = self.leaf_type.span();
+            let cfgs: Vec<_> = cfg_attrs(&self.common.variants).collect();
+            quote_spanned!(span =>
+                #(#cfgs)*
+                const _: () = {
+                    #from
+                };
+
+                #(#cfgs)*
+                #[allow(deprecated)]
+                const _: () = {
+                    #tokens
+                };
+            )
+        } else {
+            quote!(#from #tokens)
+        }
+    }
+
+    // Wraps `inner` in the variants in `levels`, outermost first.
+    // If `allocate`, wrapped fields are allocated, giving the conversion expression.
+    // Otherwise the result is the matching pattern, with `inner` binding the wrapper of the last variant.
+    pub fn nest(&self, levels: Range<usize>, inner: TokenStream, allocate: bool) -> TokenStream {
+        let span = self.leaf_type.span();
+
+        let mut nested = inner;
+        for variant in self
+            .common
+            .variants
+            .iter()
+            .take(levels.end)
+            .skip(levels.start)
+            .rev()
+        {
+            if allocate && let Some(function) = &variant.function {
+                let function_path = &function.path;
+                nested = quote_spanned!(span => #function_path(#nested));
+            }
+            if allocate && let Some(wrapper) = variant.through_wrapper() {
+                let wrapper_path = &wrapper.path;
+                nested = quote_spanned!(span => #wrapper_path::new(#nested));
+            }
+
+            let path = &variant.path;
+            nested = match &variant.field {
+                Some((_dot, AmassMember::Single(field))) => match &variant.fills {
+                    Some((_brace, fills)) if allocate => {
+                        quote_spanned!(span => #path { #field: #nested, #fills })
+                    }
+                    Some(_) => quote_spanned!(span => #path { #field: #nested, .. }),
+                    None => quote_spanned!(span => #path { #field: #nested }),
+                },
+                // The tuple is destructured into the fields, or the fields are bound to `value_N`
+                Some((_dot, AmassMember::Tuple(_paren, members))) => {
+                    let members = members.iter();
+                    let bindings = AmassMember::tuple_bindings(members.len(), span);
+                    if allocate {
+                        quote_spanned!(span => {
+                            let (#(#bindings,)*) = #nested;
+                            #path { #(#members: #bindings),* }
+                        })
+                    } else {
+                        quote_spanned!(span => #path { #(#members: #bindings),* })
+                    }
+                }
+                None => quote_spanned!(span => #path(#nested)),
+            }
+        }
+        nested
+    }
+
+    // The leaf bound by the pattern of the last variant
+    pub fn leaf_value(&self) -> TokenStream {
+        let span = self.leaf_type.span();
+        match self
+            .common
+            .variants
+            .last()
+            .and_then(AmassVariant::tuple_members)
+        {
+            Some(members) => {
+                let bindings = AmassMember::tuple_bindings(members.len(), span);
+                quote_spanned!(span => (#(#bindings,)*))
+            }
+            None => quote_spanned!(span => value),
+        }
+    }
+
+    // Variants which can be matched by a single pattern, split after each wrapped variant
+    fn segments(&self) -> Vec<Range<usize>> {
+        let mut segments = vec![];
+        let mut start = 0;
+        for (index, variant) in self.common.variants.iter().enumerate() {
+            if variant.through_wrapper().is_some() {
+                segments.push(start..index + 1);
+                start = index + 1;
+            }
+        }
+        segments.push(start..self.common.variants.len());
+        segments
+    }
+
+    // The wrapper at the end of `segment`, unless it is the final segment
+    fn segment_wrapper(&self, segment: &Range<usize>) -> Option<&AmassWrapper> {
+        segment
+            .end
+            .checked_sub(1)
+            .and_then(|last| self.common.variants.get(last))
+            .and_then(AmassVariant::through_wrapper)
+    }
+
+    /// The requested impls which can be generated for this leaf
+    fn impl_types(&self) -> Vec<ImplType> {
+        // `TryFrom<Root> for Box<Root>` would conflict with the blanket impl, as `Box<T>: From<T>`
+        let is_wrapper = self
+            .common
+            .variants
+            .last()
+            .is_some_and(AmassVariant::is_wrapper_leaf);
+
+        let is_parameter = self.common.is_type_parameter(&self.leaf_type);
+        let is_tuple = self
+            .common
+            .variants
+            .last()
+            .is_some_and(|v| v.tuple_members().is_some());
+        // The leaf cannot be recovered from a field built by a function
+        let has_function = self.common.variants.iter().any(AmassVariant::has_function);
+
+        self.common
+            .options
+            .impls
+            .iter()
+            .map(|impl_type| impl_type.impl_type())
+            .filter(|impl_type| match impl_type {
+                ImplType::From => true,
+                // `TryFrom<Root<T>> for T` is not allowed by the orphan rules
+                ImplType::TryFrom => !(is_wrapper || is_parameter || has_function),
+                // The fields of a tuple variant are not stored as a tuple, so cannot be borrowed as one
+                ImplType::AsLeaf => !(is_tuple || has_function),
+                // `Leaf<Root<T>> for T` is not allowed by the orphan rules either
+                ImplType::Leaf => !is_parameter,
+            })
+            .collect()
+    }
+
+    /// Describes each generated impl and the variants it goes through,
+    /// e.g. ``"`From<Stout> for Beer` via `Beer::Ale -> Ale::Stout`"``
+    pub fn explain(&self) -> String {
+        let leaf = self
+            .leaf_name
+            .as_ref()
+            .map_or_else(|| syn_util::display_type(&self.leaf_type), LitStr::value);
+        let root = syn_util::display_type(&self.common.root_type);
+
+        let impls: Vec<_> = self
+            .impl_types()
+            .into_iter()
+            .map(|impl_type| match impl_type {
+                ImplType::From => format!("`From<{leaf}> for {root}`"),
+                ImplType::TryFrom => format!("`TryFrom<{root}> for {leaf}`"),
+                ImplType::AsLeaf => format!("`AsLeaf<{leaf}> for {root}`"),
+                ImplType::Leaf => format!("`Leaf<{root}> for {leaf}`"),
+            })
+            .collect();
+        format!(
+            "{} via `{}`",
+            impls.join(", "),
+            describe_variants(&self.common.variants)
+        )
+    }
+
+    fn generate_from(&self) -> TokenStream {
         let Self {
             common:
                 AmassCommon {
                     parameters,
                     root_type,
-                    variants_bracket: _variants_bracket,
                     variants,
+                    ..
                 },
             leaf_type,
+            ..
         } = self;
         let span = leaf_type.span();
 
         let (impl_generics, _type_generics, where_clause) = parameters.split_for_impl();
 
-        let mut conversion = quote_spanned!(span => value);
-        for variant in variants.into_iter().rev() {
-            let path = &variant.path;
-            conversion = match &variant.named_field {
-                Some((_dot, field)) => quote_spanned!(span => #path { #field: #conversion }),
-                None => quote_spanned!(span => #path(#conversion)),
+        let value = quote_spanned!(span => value);
+        let conversion = if variants.iter().any(|v| v.deprecated().is_some()) {
+            // Stable macros cannot emit warnings, but constructing a deprecated variant can.
+            // Nested deprecated variants are constructed with the span of the root, so the warning
+            // names the variant where the root is declared, which can override the variant.
+            // The root's own deprecated variants are still converted without a warning.
+            let mut warned = AmassFrom {
+                common: self.common.clone(),
+                leaf_type: leaf_type.clone(),
+                leaf_name: None,
+            };
+            for variant in warned.common.variants.iter_mut().skip(1) {
+                if variant.deprecated().is_some() {
+                    variant.respan_path(root_type.span());
+                }
             }
-        }
+            let nested = warned.nest(1..variants.len(), value, true);
+            let root = self.nest(0..1, quote_spanned!(span => nested), true);
+            quote_spanned!(span =>
+                let nested = #nested;
+                #[allow(deprecated)]
+                let root = #root;
+                root
+            )
+        } else {
+            self.nest(0..variants.len(), value, true)
+        };
+
+        // Captured locations are those of the caller, including those captured for a nested type
+        let track_caller = (self.common.options.capture.is_some()
+            || self.common.variants.iter().any(AmassVariant::has_fills))
+        .then(|| quote_spanned!(span => #[track_caller]));
 
         quote_spanned!(span =>
             impl #impl_generics ::core::convert::From<#leaf_type> for #root_type
             #where_clause {
+                #track_caller
                 fn from(value: #leaf_type) -> Self {
                     #conversion
                 }
             }
         )
     }
+
+    fn generate_try_from(&self) -> TokenStream {
+        let Self {
+            common:
+                AmassCommon {
+                    parameters,
+                    root_type,
+                    ..
+                },
+            leaf_type,
+            ..
+        } = self;
+        let span = leaf_type.span();
+
+        let (impl_generics, _type_generics, where_clause) = parameters.split_for_impl();
+
+        let body = self.try_from_body(&self.segments());
+
+        quote_spanned!(span =>
+            impl #impl_generics ::core::convert::TryFrom<#root_type> for #leaf_type
+            #where_clause {
+                type Error = #root_type;
+
+                // The pattern is irrefutable if every enum along the path has a single variant
+                #[allow(unreachable_patterns)]
+                fn try_from(value: #root_type) -> ::core::result::Result<Self, Self::Error> {
+                    #body
+                }
+            }
+        )
+    }
+
+    // Matches `value` against the first segment. Values which do not match are rebuilt into the root.
+    fn try_from_body(&self, segments: &[Range<usize>]) -> TokenStream {
+        let span = self.leaf_type.span();
+
+        let Some((segment, rest)) = segments.split_first() else {
+            return quote_spanned!(span => ::core::result::Result::Ok(value));
+        };
+        if segment.is_empty() {
+            return quote_spanned!(span => ::core::result::Result::Ok(value));
+        }
+
+        let pattern = self.nest(segment.clone(), quote_spanned!(span => value), false);
+        let rebuild = self.nest(0..segment.start, quote_spanned!(span => value), true);
+
+        let matched = match self.segment_wrapper(segment) {
+            Some(wrapper) if !rest.is_empty() => {
+                let inner = self.try_from_body(rest);
+                match wrapper.wrapper.wrapper() {
+                    Wrapper::Box => quote_spanned!(span => {
+                        let value = *value;
+                        #inner
+                    }),
+                    Wrapper::Rc | Wrapper::Arc => {
+                        let wrapper_path = &wrapper.path;
+                        // The value is shared, so put it back where it was found
+                        let shared = self.nest(
+                            0..segment.start,
+                            self.nest(segment.clone(), quote_spanned!(span => value), false),
+                            true,
+                        );
+                        quote_spanned!(span =>
+                            match #wrapper_path::try_unwrap(value) {
+                                ::core::result::Result::Ok(value) => #inner,
+                                ::core::result::Result::Err(value) => ::core::result::Result::Err(#shared),
+                            }
+                        )
+                    }
+                }
+            }
+            _ => {
+                let leaf_value = self.leaf_value();
+                quote_spanned!(span => ::core::result::Result::Ok(#leaf_value))
+            }
+        };
+
+        quote_spanned!(span =>
+            match value {
+                #pattern => #matched,
+                value => ::core::result::Result::Err(#rebuild),
+            }
+        )
+    }
+
+    fn generate_as_leaf(&self) -> TokenStream {
+        let Self {
+            common:
+                AmassCommon {
+                    parameters,
+                    root_type,
+                    ..
+                },
+            leaf_type,
+            ..
+        } = self;
+        let span = leaf_type.span();
+
+        let (impl_generics, _type_generics, where_clause) = parameters.split_for_impl();
+
+        let segments = self.segments();
+        let body = self.as_leaf_body(quote_spanned!(span => self), &segments, false);
+        let body_mut = self.as_leaf_body(quote_spanned!(span => self), &segments, true);
+
+        quote_spanned!(span =>
+            impl #impl_generics ::amass::AsLeaf<#leaf_type> for #root_type
+            #where_clause {
+                #[allow(unreachable_patterns)]
+                fn as_leaf(&self) -> ::core::option::Option<&#leaf_type> {
+                    #body
+                }
+
+                #[allow(unreachable_patterns)]
+                fn as_leaf_mut(&mut self) -> ::core::option::Option<&mut #leaf_type> {
+                    #body_mut
+                }
+            }
+        )
+    }
+
+    fn generate_leaf(&self) -> TokenStream {
+        let Self {
+            common:
+                AmassCommon {
+                    parameters,
+                    root_type,
+                    variants,
+                    ..
+                },
+            leaf_type,
+            ..
+        } = self;
+        let span = leaf_type.span();
+
+        let (impl_generics, _type_generics, where_clause) = parameters.split_for_impl();
+
+        let path = variants.iter().map(AmassVariant::describe);
+        let depth = variants.len();
+
+        quote_spanned!(span =>
+            impl #impl_generics ::amass::__private::Sealed<#root_type> for #leaf_type
+            #where_clause {}
+
+            impl #impl_generics ::amass::Leaf<#root_type> for #leaf_type
+            #where_clause {
+                const PATH: &'static [&'static str] = &[#(#path),*];
+                const DEPTH: usize = #depth;
+            }
+        )
+    }
+
+    // Matches `scrutinee` against the first segment, dereferencing through wrappers.
+    // Shared wrappers only give mutable access if they are not shared.
+    fn as_leaf_body(
+        &self,
+        scrutinee: TokenStream,
+        segments: &[Range<usize>],
+        mutable: bool,
+    ) -> TokenStream {
+        let span = self.leaf_type.span();
+
+        let Some((segment, rest)) = segments.split_first() else {
+            return quote_spanned!(span => ::core::option::Option::Some(#scrutinee));
+        };
+        if segment.is_empty() {
+            return quote_spanned!(span => ::core::option::Option::Some(#scrutinee));
+        }
+
+        let pattern = self.nest(segment.clone(), quote_spanned!(span => value), false);
+
+        let matched = match self.segment_wrapper(segment) {
+            Some(wrapper) if !rest.is_empty() => {
+                let inner = self.as_leaf_body(quote_spanned!(span => value), rest, mutable);
+                let wrapper_path = &wrapper.path;
+                let deref = match (mutable, wrapper.wrapper.wrapper()) {
+                    (false, _) => quote_spanned!(span => &**value),
+                    (true, Wrapper::Box) => quote_spanned!(span => &mut **value),
+                    (true, Wrapper::Rc | Wrapper::Arc) => {
+                        quote_spanned!(span => #wrapper_path::get_mut(value)?)
+                    }
+                };
+                quote_spanned!(span => {
+                    let value = #deref;
+                    #inner
+                })
+            }
+            _ => quote_spanned!(span => ::core::option::Option::Some(value)),
+        };
+
+        quote_spanned!(span =>
+            match #scrutinee {
+                #pattern => #matched,
+                _ => ::core::option::Option::None,
+            }
+        )
+    }
+}
+
+/// The field of a variant which a delegated trait is forwarded to.
+/// If `deref`, the field is a wrapper and `ty` is the type inside it.
+#[derive(Clone)]
+pub(crate) struct DelegateVariant {
+    /// `cfg` attributes of the variant
+    pub cfgs: Vec<Attribute>,
+    pub deref: Option<Token![*]>,
+    pub path: Path,
+    pub dot: Token![.],
+    pub member: Member,
+    pub colon: Token![:],
+    pub ty: Type,
+}
+
+impl DelegateVariant {
+    pub fn new(cfgs: Vec<Attribute>, deref: bool, path: Path, member: Member, ty: Type) -> Self {
+        Self {
+            cfgs,
+            deref: deref.then(Default::default),
+            path,
+            dot: Default::default(),
+            member,
+            colon: Default::default(),
+            ty,
+        }
+    }
+}
+
+impl Parse for DelegateVariant {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            cfgs: input.call(Attribute::parse_outer)?,
+            deref: input.parse()?,
+            path: input.parse()?,
+            dot: input.parse()?,
+            member: input.parse()?,
+            colon: input.parse()?,
+            ty: input.parse()?,
+        })
+    }
+}
+
+impl ToTokens for DelegateVariant {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            cfgs,
+            deref,
+            path,
+            dot,
+            member,
+            colon,
+            ty,
+        } = self;
+        for cfg in cfgs {
+            cfg.to_tokens(tokens);
+        }
+        deref.to_tokens(tokens);
+        path.to_tokens(tokens);
+        dot.to_tokens(tokens);
+        member.to_tokens(tokens);
+        colon.to_tokens(tokens);
+        ty.to_tokens(tokens);
+    }
+}
+
+pub(crate) struct AmassDelegate {
+    pub trait_path: Path,
+    pub semi: Token![;],
+    pub parameters: Generics,
+    pub root_type: Type,
+    pub variants_bracket: Bracket,
+    // [
+    pub variants: Punctuated<DelegateVariant, Token![,]>,
+    // ]
+    /// Absent until the trait is reflected
+    pub reflected: Option<Item>,
+}
+
+impl AmassDelegate {
+    pub fn new(
+        trait_path: Path,
+        parameters: Generics,
+        root_type: Type,
+        variants: Punctuated<DelegateVariant, Token![,]>,
+    ) -> Self {
+        Self {
+            trait_path,
+            semi: Default::default(),
+            parameters,
+            root_type,
+            variants_bracket: Default::default(),
+            variants,
+            reflected: None,
+        }
+    }
+}
+
+impl Parse for AmassDelegate {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let variants_content;
+        Ok(Self {
+            trait_path: input.parse()?,
+            semi: input.parse()?,
+            parameters: input.parse()?,
+            root_type: input.parse()?,
+            variants_bracket: bracketed!(variants_content in input),
+            variants: Punctuated::parse_terminated(&variants_content)?,
+            reflected: if input.is_empty() {
+                None
+            } else {
+                Some(input.parse()?)
+            },
+        })
+    }
+}
+
+impl ToTokens for AmassDelegate {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            trait_path,
+            semi,
+            parameters,
+            root_type,
+            variants_bracket,
+            variants,
+            reflected,
+        } = self;
+        trait_path.to_tokens(tokens);
+        semi.to_tokens(tokens);
+        parameters.to_tokens(tokens);
+        root_type.to_tokens(tokens);
+        variants_bracket.surround(tokens, |ts| variants.to_tokens(ts));
+        reflected.to_tokens(tokens);
+    }
 }
 
 pub(crate) struct AmassApply {
     pub common: AmassCommon,
-    pub generic_arguments: AngleBracketedGenericArguments,
-    pub telety_item: Item,
+    /// Only present if [AmassCommon::is_sequential]
+    pub traversal: Option<AmassTraversal>,
+    /// Only present if not [AmassCommon::is_sequential].
+    /// The reflected type as a leaf of the root, unless it is the root.
+    pub leaf_type: Option<Type>,
+    /// Only present with `leaf_type`, if the root should `explain` its conversions
+    pub leaf_name: Option<LitStr>,
+    /// Absent if the last nested type could not be reflected
+    pub reflected: Option<(AngleBracketedGenericArguments, Item)>,
 }
 
 impl Parse for AmassApply {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let common: AmassCommon = input.parse()?;
+        let mut leaf_type = None;
+        let mut leaf_name = None;
+        let traversal = if common.is_sequential() {
+            Some(input.parse()?)
+        } else {
+            let leaf_type_content;
+            parenthesized!(leaf_type_content in input);
+            if !leaf_type_content.is_empty() {
+                leaf_type = Some(leaf_type_content.parse()?);
+                leaf_name = leaf_type_content.parse()?;
+            }
+            None
+        };
+        let reflected = if input.is_empty() {
+            None
+        } else {
+            Some((input.parse()?, input.parse()?))
+        };
+
         Ok(Self {
-            common: input.parse()?,
-            generic_arguments: input.parse()?,
-            telety_item: input.parse()?,
+            common,
+            traversal,
+            leaf_type,
+            leaf_name,
+            reflected,
         })
     }
 }
@@ -192,11 +1413,22 @@ impl ToTokens for AmassApply {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let Self {
             common,
-            generic_arguments,
-            telety_item: telety_enum,
+            traversal,
+            leaf_type,
+            leaf_name,
+            reflected,
         } = self;
         common.to_tokens(tokens);
-        generic_arguments.to_tokens(tokens);
-        telety_enum.to_tokens(tokens);
+        match traversal {
+            Some(traversal) => traversal.to_tokens(tokens),
+            None => Paren::default().surround(tokens, |ts| {
+                leaf_type.to_tokens(ts);
+                leaf_name.to_tokens(ts);
+            }),
+        }
+        if let Some((generic_arguments, telety_item)) = reflected {
+            generic_arguments.to_tokens(tokens);
+            telety_item.to_tokens(tokens);
+        }
     }
 }
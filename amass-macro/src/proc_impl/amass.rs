@@ -1,21 +1,67 @@
 use proc_macro2::TokenStream;
-use quote::{quote, quote_spanned};
+use quote::{ToTokens as _, format_ident, quote, quote_spanned};
 use syn::{
-    Attribute, Ident, ItemEnum, Path, Type, TypePath, parse_quote, parse_quote_spanned, parse2,
+    Attribute, Field, Fields, Ident, Index, Item, Member, Path, Token, Type, TypePath, parse_quote,
+    parse_quote_spanned, parse2,
+    punctuated::Punctuated,
     spanned::Spanned as _,
     visit_mut::{self, VisitMut as _},
 };
 
-use crate::{options::Options, parse::AmassCommon, syn_util};
+use super::{amass_apply::marked_field, traversal::AmassTraversal};
+use crate::{
+    capture::{Capture, CaptureParse},
+    on_conflict::{OnConflict, OnConflictParse},
+    options::{Options, RootOptions},
+    parse::{AmassCommon, AmassDelegate, DelegateVariant},
+    syn_util,
+    variant_action::VariantOptions,
+    wrapper::Wrapper,
+};
 
 pub(crate) fn amass(attr_args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
     let attr_span = attr_args.span();
 
-    let _options: Options = parse2(attr_args)?;
-    let mut item: ItemEnum = parse2(item)?;
+    let options: Options = parse2(attr_args)?;
+    let mut item: Item = parse2(item)?;
 
-    let (_, type_generics, _) = item.generics.split_for_impl();
-    let item_ident = item.ident.clone();
+    let (item_vis, item_ident, generics, variant_attrs) = match &item {
+        Item::Enum(item) => (
+            &item.vis,
+            &item.ident,
+            &item.generics,
+            item.variants.iter().map(|v| &v.attrs).collect::<Vec<_>>(),
+        ),
+        Item::Struct(item) => {
+            let mut fields = item.fields.iter();
+            let (Some(field), None) = (fields.next(), fields.next()) else {
+                return Err(syn::Error::new(
+                    item.fields.span(),
+                    "Only single-field structs can be amassed",
+                ));
+            };
+            (&item.vis, &item.ident, &item.generics, vec![&field.attrs])
+        }
+        item => {
+            return Err(syn::Error::new(
+                item.span(),
+                "Only enums and structs can be amassed",
+            ));
+        }
+    };
+
+    // The flat enum would need every parameter of the root to appear in a leaf
+    if let Some(flat) = &options.flat
+        && !generics.params.is_empty()
+    {
+        return Err(syn::Error::new(
+            flat.span(),
+            "Generic types cannot be flattened",
+        ));
+    }
+
+    let (_, type_generics, _) = generics.split_for_impl();
+    let item_ident = item_ident.clone();
     let item_path: Path = parse_quote!(self::#item_ident);
     let item_path_generics: Path = parse_quote!(#item_path #type_generics);
     let item_type = Type::Path(TypePath {
@@ -23,9 +69,38 @@ pub(crate) fn amass(attr_args: TokenStream, item: TokenStream) -> syn::Result<To
         path: item_path_generics,
     });
 
-    let common = AmassCommon::new(item.generics.clone(), item_type);
+    // Marking a variant as preferred opts in to conflict resolution
+    let mut on_conflict = options.on_conflict.clone();
+    if on_conflict.is_none() {
+        for attrs in variant_attrs {
+            if VariantOptions::from_attrs(attrs)?.prefer {
+                on_conflict = Some(OnConflictParse::new(OnConflict::Prefer, attr_span));
+                break;
+            }
+        }
+    }
+
+    let root_options = RootOptions {
+        impls: options.impls_or_default(attr_span),
+        overrides: options.overrides.unwrap_or_default(),
+        on_conflict,
+        on_cycle: options.on_cycle,
+        capture: options.capture.clone(),
+        max_depth: options.max_depth,
+        kind: options.kind.clone(),
+        flat: options.flat.clone(),
+        conversions: options.conversions.clone(),
+        explain: options.explain.clone(),
+    };
+    let common = AmassCommon::new(root_options, generics.clone(), item_type.clone());
+    // The root is not a leaf
+    let state = if common.is_sequential() {
+        AmassTraversal::default().into_token_stream()
+    } else {
+        quote!(())
+    };
 
-    let args = syn_util::generic_params_to_arguments(&item.generics);
+    let args = syn_util::generic_params_to_arguments(generics);
 
     let needle = Ident::new("__amass_apply_needle", attr_span);
 
@@ -36,6 +111,7 @@ pub(crate) fn amass(attr_args: TokenStream, item: TokenStream) -> syn::Result<To
             quote_spanned! { attr_span =>
                 ::amass::__private::amass_apply!(
                     #common
+                    #state
                     <#args>
                     #needle
                 );
@@ -44,22 +120,378 @@ pub(crate) fn amass(attr_args: TokenStream, item: TokenStream) -> syn::Result<To
         .with_fallback(quote!(::amass::__private::require_telety_error!();))
         .with_telety_path(parse_quote!(::amass::__private::telety));
 
+    let path_impl = if options.path {
+        amass_path_impl(&item)
+    } else {
+        TokenStream::new()
+    };
+
+    let capture = options.capture.as_ref().map(CaptureParse::capture);
+    let forwarded = forwarded_fields(&item, capture)?;
+
+    let delegates = match &options.delegate {
+        Some(traits) => {
+            let variants = delegate_variants(&forwarded)?;
+            traits
+                .iter()
+                .map(|trait_path| {
+                    let delegate = AmassDelegate::new(
+                        trait_path.clone(),
+                        generics.clone(),
+                        item_type.clone(),
+                        variants.clone(),
+                    );
+                    // Traits in scope by name are reflected through the module
+                    let macro_path: Path =
+                        if trait_path.leading_colon.is_none() && trait_path.segments.len() == 1 {
+                            parse_quote!(self::#trait_path)
+                        } else {
+                            trait_path.clone()
+                        };
+                    let needle = Ident::new("__amass_delegate_needle", attr_span);
+                    telety::v1::TY
+                        .apply(
+                            macro_path,
+                            needle.clone(),
+                            quote_spanned! { attr_span =>
+                                ::amass::__private::amass_delegate!(#delegate #needle);
+                            },
+                        )
+                        .with_fallback(quote!(::amass::__private::require_telety_trait_error!();))
+                        .with_telety_path(parse_quote!(::amass::__private::telety))
+                })
+                .collect()
+        }
+        None => vec![],
+    };
+
+    let error_impl = if options.error {
+        error_impl(&item, &forwarded)?
+    } else {
+        TokenStream::new()
+    };
+
+    // The traversal exports a macro declaring each generated enum, named after the root's unique ident.
+    // They are invoked in a module beside the root, and the enums are as visible as the root.
+    let generated: Vec<_> = options.kind.iter().chain(&options.flat).collect();
+    let generated_module = if generated.is_empty() {
+        TokenStream::new()
+    } else {
+        let module = format_ident!("__amass_{item_ident}");
+        let unique_ident = Ident::new("__amass_unique_ident", attr_span);
+        let declarations = generated.iter().map(|ident| {
+            telety::v1::UNIQUE_IDENT.apply(
+                parse_quote!(super::#item_ident),
+                unique_ident.clone(),
+                quote!(::amass::__private::amass_generated!(#unique_ident #ident);),
+            )
+        });
+        quote! {
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            mod #module {
+                use crate::*;
+
+                #(#declarations)*
+            }
+
+            #(#item_vis use self::#module::#generated;)*
+        }
+    };
+
     // Strip "helper" attributes because attribute macros still aren't allowed to have them :(
     // https://github.com/rust-lang/rust/issues/65823
-    HelperAttributeVisitor.visit_item_enum_mut(&mut item);
+    HelperAttributeVisitor.visit_item_mut(&mut item);
 
     Ok(quote! {
         #item
 
+        #path_impl
+
+        #generated_module
+
         #macro_ts
+
+        #(#delegates)*
+
+        #error_impl
+    })
+}
+
+/// `amass_path` follows the variants of the value, continuing into fields of types which also generate it
+fn amass_path_impl(item: &Item) -> TokenStream {
+    let (item_ident, generics, arms) = match item {
+        Item::Enum(item) => {
+            let arms = item.variants.iter().map(|variant| {
+                let variant_ident = &variant.ident;
+                let name = variant_ident.to_string();
+                let cfgs = cfg_attrs(&variant.attrs);
+                let mut fields = variant.fields.iter();
+                match (fields.next(), fields.next()) {
+                    (Some(field), None) => {
+                        let member = field_member(field, 0);
+                        quote!(#(#cfgs)* Self::#variant_ident { #member: ref value } => {
+                            path.push(#name);
+                            (&::amass::__private::PathProbe(value)).push_field_path(path);
+                        })
+                    }
+                    _ => quote!(#(#cfgs)* Self::#variant_ident { .. } => path.push(#name),),
+                }
+            });
+            (&item.ident, &item.generics, quote!(#(#arms)*))
+        }
+        // Structs have no variants of their own
+        Item::Struct(item) => {
+            let Some(field) = item.fields.iter().next() else {
+                return TokenStream::new();
+            };
+            let member = field_member(field, 0);
+            let arm = quote!(Self { #member: ref value } => {
+                (&::amass::__private::PathProbe(value)).push_field_path(path);
+            });
+            (&item.ident, &item.generics, arm)
+        }
+        _ => return TokenStream::new(),
+    };
+
+    let name = item_ident.to_string();
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #item_ident #type_generics #where_clause {
+            /// The variants of this value, continuing into fields of types which also have `amass_path`
+            pub fn amass_path(&self) -> ::amass::AmassPath {
+                let mut path = ::amass::AmassPath::new(#name);
+                ::amass::__private::AmassPathed::push_path(self, &mut path);
+                path
+            }
+        }
+
+        impl #impl_generics ::amass::__private::AmassPathed for #item_ident #type_generics #where_clause {
+            #[allow(unused_variables, deprecated)]
+            fn push_path(&self, path: &mut ::amass::AmassPath) {
+                #[allow(unused_imports)]
+                use ::amass::__private::{PathProbeLeaf as _, PathProbeNested as _};
+                match *self {
+                    #arms
+                }
+            }
+        }
+    }
+}
+
+/// A variant of the root, with the field which traits are forwarded to, if it has one.
+/// Like conversions, this is the field marked `#[amass_field]`, or the only field which is not captured.
+struct Forwarded<'a> {
+    path: Path,
+    ident: &'a Ident,
+    attrs: &'a [Attribute],
+    cfgs: Vec<&'a Attribute>,
+    field: Option<DelegateVariant>,
+}
+
+fn forwarded_fields(item: &Item, capture: Option<Capture>) -> syn::Result<Vec<Forwarded<'_>>> {
+    let variants: Vec<(Path, &Ident, &[Attribute], &Fields)> = match item {
+        Item::Enum(item) => item
+            .variants
+            .iter()
+            .map(|variant| {
+                let ident = &variant.ident;
+                (
+                    parse_quote!(Self::#ident),
+                    ident,
+                    variant.attrs.as_slice(),
+                    &variant.fields,
+                )
+            })
+            .collect(),
+        Item::Struct(item) => vec![(
+            parse_quote!(Self),
+            &item.ident,
+            item.attrs.as_slice(),
+            &item.fields,
+        )],
+        _ => vec![],
+    };
+
+    let mut forwarded = vec![];
+    for (path, ident, attrs, fields) in variants {
+        // The struct's own `cfg` applies to its impls anyway
+        let cfgs: Vec<_> = match item {
+            Item::Enum(_) => cfg_attrs(attrs).collect(),
+            _ => vec![],
+        };
+        let marked = marked_field(fields, capture.as_slice())?;
+        let mut field_iter = fields.iter().enumerate();
+        let field = match (marked, field_iter.next(), field_iter.next()) {
+            (Some((index, field)), _, _) | (None, Some((index, field)), None) => {
+                let (deref, ty) = match &field.ty {
+                    Type::Path(type_path) => match Wrapper::from_type_path(type_path) {
+                        Some((_wrapper, inner)) => (true, Type::Path(inner.clone())),
+                        None => (false, field.ty.clone()),
+                    },
+                    ty => (false, ty.clone()),
+                };
+                Some(DelegateVariant::new(
+                    cfgs.iter().copied().cloned().collect(),
+                    deref,
+                    path.clone(),
+                    field_member(field, index),
+                    ty,
+                ))
+            }
+            _ => None,
+        };
+        forwarded.push(Forwarded {
+            path,
+            ident,
+            attrs,
+            cfgs,
+            field,
+        });
+    }
+    Ok(forwarded)
+}
+
+/// The field of each variant which delegated traits are forwarded to
+fn delegate_variants(
+    forwarded: &[Forwarded],
+) -> syn::Result<Punctuated<DelegateVariant, Token![,]>> {
+    forwarded
+        .iter()
+        .map(|forwarded| {
+            forwarded.field.clone().ok_or_else(|| {
+                syn::Error::new(
+                    forwarded.ident.span(),
+                    "Variant must have a single field to delegate to (or a field marked `#[amass_field]`)",
+                )
+            })
+        })
+        .collect()
+}
+
+/// `Display` writes the prefix of the variant, followed by the field, if it has one.
+/// Variants without a field write their prefix, or their name.
+/// `source` is the field, if the variant has one.
+fn error_impl(item: &Item, forwarded: &[Forwarded]) -> syn::Result<TokenStream> {
+    let (item_ident, generics) = match item {
+        Item::Enum(item) => (&item.ident, &item.generics),
+        Item::Struct(item) => (&item.ident, &item.generics),
+        _ => return Ok(TokenStream::new()),
+    };
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let predicates = where_clause.map(|w| &w.predicates);
+
+    let mut display_arms = vec![];
+    let mut source_arms = vec![];
+    let mut display_bounds = vec![];
+    let mut error_bounds = vec![];
+    let mut checks = vec![];
+    for forwarded in forwarded {
+        let prefix = VariantOptions::from_attrs(forwarded.attrs)?.prefix;
+        let path = &forwarded.path;
+        let cfgs = &forwarded.cfgs;
+
+        match &forwarded.field {
+            Some(DelegateVariant {
+                deref, member, ty, ..
+            }) => {
+                let value = match deref {
+                    Some(_) => quote!(&**value),
+                    None => quote!(value),
+                };
+                let prefix = prefix.map(|prefix| quote!(::core::write!(f, "{}: ", #prefix)?;));
+                display_arms.push(quote!(#(#cfgs)* #path { #member: ref value, .. } => {
+                    #prefix
+                    ::core::fmt::Display::fmt(#value, f)
+                }));
+                source_arms.push(quote!(
+                    #(#cfgs)* #path { #member: ref value, .. } => ::core::option::Option::Some(#value),
+                ));
+                // Only fields which mention a parameter are bounded, as bounds on recursive types overflow,
+                // so the others are checked separately
+                let mentions_self = syn_util::mentions_self(ty.to_token_stream());
+                match (mentions_self, syn_util::mentions_parameter(generics, ty)) {
+                    // The field is the root, which these impls are for
+                    (true, _) => {}
+                    (false, true) if cfgs.is_empty() => {
+                        display_bounds.push(syn_util::respan(
+                            quote!(#ty: ::core::fmt::Display),
+                            ty.span(),
+                        ));
+                        error_bounds.push(syn_util::respan(
+                            quote!(#ty: ::std::error::Error + 'static),
+                            ty.span(),
+                        ));
+                    }
+                    // Bounds cannot be conditional, so these fields are checked by the arms instead
+                    (false, true) => {}
+                    (false, false) => {
+                        let check =
+                            syn_util::check_bounds(ty, quote!(::std::error::Error + 'static));
+                        checks.push(quote!(#(#cfgs)* #check));
+                    }
+                }
+            }
+            None => {
+                let text =
+                    prefix.map_or_else(|| forwarded.ident.to_string(), |prefix| prefix.value());
+                display_arms.push(quote!(#(#cfgs)* #path { .. } => f.write_str(#text),));
+                source_arms.push(quote!(#(#cfgs)* #path { .. } => ::core::option::Option::None,));
+            }
+        }
+    }
+
+    Ok(quote! {
+        #[allow(deprecated)]
+        impl #impl_generics ::core::fmt::Display for #item_ident #type_generics
+        where
+            #predicates
+            #(#display_bounds,)*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match *self {
+                    #(#display_arms)*
+                }
+            }
+        }
+
+        #[allow(deprecated)]
+        impl #impl_generics ::std::error::Error for #item_ident #type_generics
+        where
+            #predicates
+            #(#error_bounds,)*
+        {
+            fn source(&self) -> ::core::option::Option<&(dyn ::std::error::Error + 'static)> {
+                match *self {
+                    #(#source_arms)*
+                }
+            }
+        }
+
+        #(#checks)*
     })
 }
 
+fn cfg_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("cfg"))
+}
+
+fn field_member(field: &Field, index: usize) -> Member {
+    match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(index)),
+    }
+}
+
 struct HelperAttributeVisitor;
 
 impl HelperAttributeVisitor {
     fn is_helper(ident: &Ident) -> bool {
-        matches!(ident.to_string().as_str(), "amass_action")
+        matches!(
+            ident.to_string().as_str(),
+            "amass_action" | "amass_field" | "amass_fill"
+        )
     }
 
     fn make_noop_attr(attr: &mut Attribute) {
@@ -71,6 +503,7 @@ impl HelperAttributeVisitor {
 }
 
 impl visit_mut::VisitMut for HelperAttributeVisitor {
+    #[allow(clippy::collapsible_if)]
     fn visit_attribute_mut(&mut self, i: &mut Attribute) {
         if let Some(ident) = i.meta.path().get_ident() {
             if Self::is_helper(ident) {
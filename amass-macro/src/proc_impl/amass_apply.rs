@@ -1,13 +1,27 @@
-use proc_macro2::{Ident, TokenStream};
-use quote::{ToTokens, quote, quote_spanned};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{ToTokens, format_ident, quote, quote_spanned};
 use syn::{
-    Field, Fields, Item, Token, parse_quote, parse2, punctuated::Punctuated, spanned::Spanned as _,
+    AngleBracketedGenericArguments, Expr, Field, FieldValue, Fields, Index, Item, ItemStruct,
+    LitStr, Member, Path, Token, Type, TypePath, Variant, parse_quote, parse_quote_spanned, parse2,
+    punctuated::Punctuated, spanned::Spanned as _, visit_mut::VisitMut as _,
 };
+use telety::visitor::ApplyGenericArguments;
 
+use super::{
+    conversions::generate_conversions,
+    explain::generate_explain,
+    flat::generate_flat,
+    kind::generate_kind,
+    traversal::{AmassClaim, AmassFrame, AmassNamed},
+};
 use crate::{
+    capture::{Capture, CaptureParse},
+    on_cycle::OnCycle,
     options::Options,
-    parse::{AmassApply, AmassFrom, AmassVariant},
-    variant_action::VariantAction,
+    parse::{self, AmassApply, AmassCommon, AmassFrom, AmassVariant},
+    syn_util::{self, QualifyPaths},
+    variant_action::{VariantAction, VariantOptions},
+    wrapper::Wrapper,
 };
 
 pub(crate) fn amass_apply(attr: TokenStream) -> syn::Result<TokenStream> {
@@ -15,36 +29,454 @@ pub(crate) fn amass_apply(attr: TokenStream) -> syn::Result<TokenStream> {
 
     let AmassApply {
         mut common,
-        generic_arguments,
-        telety_item,
+        traversal,
+        leaf_type,
+        leaf_name,
+        reflected,
     } = parse2(attr)?;
 
-    let telety = telety::Telety::new(&telety_item)?;
+    let Some(mut traversal) = traversal else {
+        let (generic_arguments, telety_item) =
+            reflected.ok_or_else(|| syn::Error::new(span, "Expected a reflected item"))?;
+        return apply_parallel(
+            span,
+            common,
+            leaf_type,
+            leaf_name,
+            &generic_arguments,
+            &telety_item,
+        );
+    };
+
+    match reflected {
+        Some((generic_arguments, telety_item)) => {
+            let reflection = reflect(&common, &generic_arguments, &telety_item)?;
+            // The root is reflected first
+            if common.variants().is_empty() {
+                traversal.set_unique_ident(reflection.unique_ident.clone());
+            }
+            if reflection.cyclic {
+                traversal.remove_claim(common.variants());
+            }
+            let children = reflection
+                .amassed
+                .into_iter()
+                .map(|amassed| {
+                    let mut variants = common.variants().clone();
+                    variants.push(amassed.variant);
+                    let frame = amassed.leaf.nested.map(|nested| {
+                        AmassFrame::new(
+                            nested.force,
+                            variants.clone(),
+                            nested.macro_path,
+                            nested.generic_arguments,
+                        )
+                    });
+                    let claim = AmassClaim::new(
+                        amassed.prefer,
+                        amassed.leaf.key,
+                        variants,
+                        amassed.leaf.leaf_type,
+                    );
+                    (claim, frame)
+                })
+                .collect();
+            traversal.reflect(common.variants(), Some(reflection.path), children);
+            traversal.reflect_kinds(common.variants(), reflection.kinds);
+            traversal.reflect_flat(common.variants(), reflection.flat);
+        }
+        None => traversal.reflect(common.variants(), None, vec![]),
+    }
+
+    let Some(frame) = traversal.pop_frame() else {
+        let mut generate = || -> syn::Result<TokenStream> {
+            // Conflicting leaves are reported first, as they also cause any clashing kind or flat variants
+            let claims = traversal.resolve(common.on_conflict())?;
+            let kind = generate_kind(&traversal, &common)?;
+            let flat = generate_flat(&traversal, &common)?;
+            let conversions = generate_conversions(&common, &claims);
+            let amass_froms: Vec<_> = claims
+                .into_iter()
+                .map(|claim| claim.into_amass_from(common.clone()))
+                .collect();
+            let explain = generate_explain(&common, &amass_froms);
+            let generated_items = amass_froms.iter().map(|amass_from| {
+                quote_spanned!(span =>
+                    ::amass::__private::amass_from!(#amass_from);
+                )
+            });
+            Ok(quote!(#kind #flat #conversions #explain #(#generated_items)*))
+        };
+        return Ok(generate().unwrap_or_else(|error| {
+            let placeholders = traversal.generate_placeholders(&common);
+            let error = error.into_compile_error();
+            quote!(#placeholders #error)
+        }));
+    };
+
+    let AmassFrame {
+        force,
+        variants,
+        macro_path,
+        generic_arguments,
+        ..
+    } = frame;
+    common.set_variants(variants);
+
+    let fallback = if force.value {
+        quote_spanned!(span =>
+            ::amass::__private::require_telety_error!();
+        )
+    } else {
+        // Nothing to reflect, continue with the next pending type
+        quote_spanned!(span =>
+            ::amass::__private::amass_apply!(#common #traversal);
+        )
+    };
+
+    let needle = Ident::new("__amass_apply_needle", span);
+
+    let amass_apply_macro = telety::v1::TY
+        .apply(
+            macro_path,
+            needle.clone(),
+            quote_spanned!(span => {
+                ::amass::__private::amass_apply!(#common #traversal #generic_arguments #needle);
+            }),
+        )
+        .with_fallback(fallback)
+        .with_telety_path(parse_quote!(::amass::__private::telety));
+
+    Ok(amass_apply_macro.into_token_stream())
+}
+
+/// Each nested type is reflected independently, generating impls as soon as variants are found.
+/// A nested type generates its own impl once it is known not to contain itself.
+fn apply_parallel(
+    span: Span,
+    mut common: AmassCommon,
+    leaf_type: Option<Type>,
+    leaf_name: Option<LitStr>,
+    generic_arguments: &AngleBracketedGenericArguments,
+    telety_item: &Item,
+) -> syn::Result<TokenStream> {
+    let Reflection {
+        telety_path,
+        amassed,
+        cyclic,
+        ..
+    } = reflect(&common, generic_arguments, telety_item)?;
+
+    if cyclic {
+        return Ok(TokenStream::new());
+    }
+    check_duplicates(&common, &amassed)?;
+
+    let mut generated_items = vec![];
+
+    if let Some(leaf_type) = leaf_type {
+        let amass_from = AmassFrom {
+            common,
+            leaf_type,
+            leaf_name,
+        };
+
+        generated_items.push(quote_spanned!(span =>
+            ::amass::__private::amass_from!(#amass_from);
+        ));
+
+        common = amass_from.common;
+    }
+
+    for amassed in amassed {
+        // TODO should be in a drop guard
+        common.push_variant(amassed.variant);
+
+        let amass_from = AmassFrom {
+            leaf_name: common.leaf_name(&amassed.leaf.key),
+            common,
+            leaf_type: amassed.leaf.leaf_type,
+        };
+
+        if let Some(Nested {
+            force,
+            macro_path,
+            generic_arguments,
+        }) = amassed.leaf.nested
+        {
+            let fallback = if force {
+                quote_spanned!( span =>
+                    ::amass::__private::require_telety_error!();
+                )
+            } else {
+                quote_spanned!(span =>
+                    ::amass::__private::amass_from!(#amass_from);
+                )
+            };
+
+            let AmassFrom {
+                common,
+                leaf_type,
+                leaf_name,
+            } = &amass_from;
+
+            let needle = Ident::new("__amass_apply_needle", span);
+
+            let mut amass_apply_macro = telety::v1::TY
+                .apply(
+                    macro_path,
+                    needle.clone(),
+                    quote_spanned!(span => {
+                        ::amass::__private::amass_apply!(#common (#leaf_type #leaf_name) #generic_arguments #needle);
+                    }),
+                )
+                .with_fallback(fallback);
+
+            if let Some(telety_path) = telety_path.as_ref() {
+                amass_apply_macro = amass_apply_macro.with_telety_path(telety_path.clone());
+            }
+
+            generated_items.push(amass_apply_macro.into_token_stream());
+        } else {
+            generated_items.push(quote_spanned!(span =>
+                ::amass::__private::amass_from!(#amass_from);
+            ));
+        }
+
+        common = amass_from.common;
+        common.pop_variant();
+    }
+
+    Ok(quote!(#(#generated_items)*))
+}
+
+/// Variants of the same type with the same field type would generate conflicting impls.
+/// Types are compared after aliasing, so types written differently in one module are still found.
+fn check_duplicates(common: &AmassCommon, amassed: &[Amassed]) -> syn::Result<()> {
+    let leaf_types: Vec<_> = amassed
+        .iter()
+        .map(|amassed| amassed.leaf.leaf_type.to_token_stream().to_string())
+        .collect();
+    let chain = |amassed: &Amassed| {
+        let mut variants = common.variants().clone();
+        variants.push(amassed.variant.clone());
+        variants
+    };
+
+    let mut error: Option<syn::Error> = None;
+    for (index, leaf_type) in leaf_types.iter().enumerate() {
+        let conditional = |i: usize| parse::is_conditional([&amassed[i].variant]);
+        if leaf_types[..index].contains(leaf_type) || conditional(index) {
+            continue;
+        }
+        if let Some(other) =
+            (index + 1..amassed.len()).find(|&i| leaf_types[i] == *leaf_type && !conditional(i))
+        {
+            let e = parse::duplicate_error(
+                &amassed[index].leaf.key,
+                &chain(&amassed[index]),
+                &chain(&amassed[other]),
+            );
+            match &mut error {
+                Some(error) => error.combine(e),
+                None => error = Some(e),
+            }
+        }
+    }
+    error.map_or(Ok(()), Err)
+}
+
+struct Reflection {
+    /// The canonical path of the reflected type
+    path: Path,
+    /// Unique to the reflected type within its crate
+    unique_ident: Ident,
+    telety_path: Option<Path>,
+    amassed: Vec<Amassed>,
+    /// The type already contains the current type, so it is not amassed
+    cyclic: bool,
+    /// The kind of each variant, if the root has a kind enum
+    kinds: Vec<AmassNamed>,
+    /// The flat variant of each variant, if the root has a flat enum
+    flat: Vec<AmassNamed>,
+}
+
+/// A single-field variant which should be converted into the root
+struct Amassed {
+    variant: AmassVariant,
+    leaf: Leaf,
+    prefer: bool,
+}
+
+struct Leaf {
+    leaf_type: Type,
+    /// The leaf type as written, qualified to be comparable across modules
+    key: Type,
+    nested: Option<Nested>,
+}
+
+/// A variant type which should be reflected to find further variants
+struct Nested {
+    force: bool,
+    macro_path: Path,
+    generic_arguments: AngleBracketedGenericArguments,
+}
+
+fn reflect(
+    common: &AmassCommon,
+    generic_arguments: &AngleBracketedGenericArguments,
+    telety_item: &Item,
+) -> syn::Result<Reflection> {
+    let telety = telety::Telety::new(telety_item)?;
     let mut apply_args_visitor = telety.generics_visitor(&generic_arguments.args)?;
 
+    // Variants of a root parameter with an action, which cannot be skipped silently
+    let mut explicit_parameters = vec![];
+    let mut reflection = Reflection {
+        path: telety.path(),
+        unique_ident: telety.alias_map().unique_ident().clone(),
+        telety_path: telety.options().telety_path.clone(),
+        amassed: vec![],
+        cyclic: false,
+        kinds: vec![],
+        flat: vec![],
+    };
+
     let options = match Options::from_attrs(telety.attributes()) {
         Ok(options) => options,
-        Err(_) => return Ok(TokenStream::new()),
+        Err(_) => return Ok(reflection),
     };
 
-    let Item::Enum(amass_enum) = telety.item() else {
-        return Err(syn::Error::new(
-            telety.item().span(),
-            "Only enums can be amassed",
-        ));
+    // A struct is amassed like an enum with a single variant
+    let (item_ident, generics, variants, is_struct) = match telety.item() {
+        Item::Enum(item) => (
+            &item.ident,
+            &item.generics,
+            item.variants.iter().cloned().collect(),
+            false,
+        ),
+        Item::Struct(item) => (
+            &item.ident,
+            &item.generics,
+            vec![struct_variant(item)],
+            true,
+        ),
+        item => {
+            return Err(syn::Error::new(
+                item.span(),
+                "Only enums and structs can be amassed",
+            ));
+        }
     };
 
-    let mut generated_items = vec![];
-
     let primary_path = telety
         .alias_map()
         .get_self()
         .expect("Self must be aliased")
         .to_macro_path();
 
-    for variant in amass_enum.variants.iter() {
-        let variant_action = VariantAction::from_attrs(&variant.attrs)?
-            .unwrap_or(options.default.unwrap_or_default());
+    // Reflecting a type which contains the current type would never end.
+    // A wrapped enclosing type is only converted as the wrapper, so it is not reported as a cycle.
+    if common.contains_item(&primary_path) {
+        let through_wrapper = common
+            .variants()
+            .last()
+            .is_some_and(|variant| variant.through().is_some());
+        return match common.on_cycle() {
+            OnCycle::Error if !through_wrapper => Err(syn::Error::new(
+                common
+                    .variants()
+                    .last()
+                    .map_or_else(Span::call_site, AmassVariant::span),
+                format!(
+                    "`{item_ident}` contains itself through `{}` \
+                    (remove `on_cycle = error` to skip recursive variants)",
+                    common.describe_variants(),
+                ),
+            )),
+            _ => {
+                reflection.cyclic = true;
+                Ok(reflection)
+            }
+        };
+    }
+
+    // Fields are filled with the root's capture, and with the type's own capture as its own impls would
+    let mut captures: Vec<_> = common.capture().into_iter().collect();
+    captures.extend(
+        options
+            .capture
+            .as_ref()
+            .map(CaptureParse::capture)
+            .filter(|capture| !captures.contains(capture)),
+    );
+
+    for variant in &variants {
+        let variant_options = VariantOptions::from_attrs(&variant.attrs)?;
+        let explicit_action = common
+            .action_override(item_ident, variant)
+            .or(variant_options.action);
+        let variant_action = explicit_action.unwrap_or(options.default.unwrap_or_default());
+
+        if variant_options.with.is_some()
+            && matches!(variant_action, VariantAction::Tuple | VariantAction::Force)
+        {
+            return Err(syn::Error::new(
+                variant.span(),
+                "Variants converted `with` a function cannot be `tuple` or `force`",
+            ));
+        }
+        if variant_options.also_from.is_some()
+            && (variant_action == VariantAction::Tuple || variant_options.with.is_some())
+        {
+            return Err(syn::Error::new(
+                variant.span(),
+                "`also_from` cannot be combined with `tuple` or `with`",
+            ));
+        }
+        // A root variant marked `prefer` enables resolution, so this is only reached for nested variants
+        if variant_options.prefer && common.on_conflict().is_none() {
+            return Err(syn::Error::new(
+                variant.span(),
+                format!(
+                    "`prefer` on `{item_ident}::{}` would be ignored, \
+                    as the root does not resolve conflicts (add `on_conflict = prefer` to the root)",
+                    variant.ident,
+                ),
+            ));
+        }
+
+        if variant_action == VariantAction::Tuple {
+            if is_struct || variant.fields.is_empty() {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "Only enum variants with fields can be converted from a tuple",
+                ));
+            }
+
+            let field_types: Vec<_> = variant.fields.iter().map(|field| &field.ty).collect();
+            // Only a one-element tuple needs the trailing comma
+            let tuple_type: Type = if let [field_type] = field_types.as_slice() {
+                parse_quote!((#field_type,))
+            } else {
+                parse_quote!((#(#field_types),*))
+            };
+            if let Some(leaf) = non_path_type(&telety, &mut apply_args_visitor, &tuple_type) {
+                reflection.amassed.push(Amassed {
+                    variant: AmassVariant::from_tuple_fields(&primary_path, item_ident, variant),
+                    leaf,
+                    prefer: variant_options.prefer,
+                });
+            }
+            continue;
+        }
+
+        // A field marked `#[amass_field]` is converted as if it were the only field
+        let marked_field = if is_struct {
+            None
+        } else {
+            marked_field(&variant.fields, &captures)?
+        };
 
         let empty_fields = Punctuated::<Field, Token![,]>::new();
         let mut field_iter = match &variant.fields {
@@ -53,105 +485,644 @@ pub(crate) fn amass_apply(attr: TokenStream) -> syn::Result<TokenStream> {
             Fields::Unit => empty_fields.iter(),
         };
 
-        let (Some(single_field), None) = (field_iter.next(), field_iter.next()) else {
-            if variant_action == VariantAction::Force {
-                return Err(syn::Error::new(
-                    variant.span(),
-                    "Variant must be a single field variant",
-                ));
-            } else {
-                continue;
+        let single_field = match (marked_field, field_iter.next(), field_iter.next()) {
+            (Some((_, field)), _, _) | (None, Some(field), None) => field,
+            _ => {
+                if variant_action == VariantAction::Force {
+                    return Err(syn::Error::new(
+                        variant.span(),
+                        "Variant must be a single field variant",
+                    ));
+                } else {
+                    continue;
+                }
             }
         };
 
-        if let Some(amass_variant) = AmassVariant::from_field(&primary_path, variant, single_field)
-        {
+        let amass_variant = if is_struct {
+            Some(AmassVariant::from_struct_field(
+                &primary_path,
+                item_ident,
+                single_field,
+            ))
+        } else {
+            let amass_variant =
+                AmassVariant::from_field(&primary_path, item_ident, variant, single_field);
+            match marked_field {
+                Some((index, _field)) => {
+                    let fills = fill_fields(&variant.fields, index, &captures)?;
+                    amass_variant.map(|v| v.with_fills(index, fills))
+                }
+                None => amass_variant,
+            }
+        };
+
+        if let Some(amass_variant) = amass_variant {
+            // The field is built from the source type, which is never reflected
+            if let Some((with, from)) = &variant_options.with {
+                if variant_action != VariantAction::Ignore {
+                    reflection.amassed.push(Amassed {
+                        variant: amass_variant.with_function(qualify_function(&telety, with)),
+                        leaf: attribute_type(&telety, &mut apply_args_visitor, from),
+                        prefer: variant_options.prefer,
+                    });
+                }
+                continue;
+            }
+
             let type_path = if let syn::Type::Path(type_path) = &single_field.ty {
                 Some(type_path)
             } else {
                 None
             };
 
-            let variant_action = VariantAction::from_attrs(&variant.attrs)?
-                .unwrap_or(options.default.unwrap_or_default());
+            let converted = reflection.amassed.len();
             if variant_action != VariantAction::Ignore {
                 if let Some(type_path) = type_path {
-                    // TODO this probably panics if user incorrectly uses a type parameter as a variant
-                    let alias = telety
-                        .alias_map()
-                        .get_alias(type_path)?
-                        .expect("type must have an alias");
-                    let ty_macro_path = alias.to_macro_path();
-                    let mut args = alias.generic_arguments().cloned();
-                    if let Some(args) = &mut args {
-                        directed_visit::visit_mut(
-                            &mut directed_visit::syn::direct::FullDefault,
-                            &mut apply_args_visitor,
-                            args,
-                        );
-                        directed_visit::visit_mut(
-                            &mut directed_visit::syn::direct::FullDefault,
-                            &mut telety.alias_map().visitor(),
-                            args,
-                        );
-                    }
-
-                    // TODO should be in a drop guard
-                    common.push_variant(amass_variant);
-
-                    let amass_from = AmassFrom {
-                        common,
-                        leaf_type: parse_quote!(#ty_macro_path #args),
+                    // The variant stack includes this variant once nested
+                    let at_max_depth = common
+                        .max_depth()
+                        .is_some_and(|max_depth| common.variants().len() + 1 >= max_depth);
+                    let nested = match variant_action {
+                        VariantAction::Deep if !at_max_depth => Some(false),
+                        VariantAction::Force if at_max_depth => {
+                            return Err(syn::Error::new(
+                                variant.ident.span(),
+                                format!(
+                                    "Variant cannot be forced beyond the maximum depth of {}",
+                                    common.max_depth().unwrap_or_default(),
+                                ),
+                            ));
+                        }
+                        VariantAction::Force => Some(true),
+                        _ => None,
                     };
 
-                    generated_items.push(quote_spanned!(span =>
-                        ::amass::__private::amass_from!(#amass_from);
-                    ));
-
-                    common = amass_from.common;
-
-                    if variant_action == VariantAction::Deep
-                        || variant_action == VariantAction::Force
-                    {
-                        let fallback = if variant_action == VariantAction::Force {
-                            quote_spanned!( span =>
-                                ::amass::__private::require_telety_error!();
-                            )
-                        } else {
-                            TokenStream::new()
-                        };
-
-                        let ty_macro_path = alias.to_macro_path();
-
-                        let needle = Ident::new("__amass_apply_needle", span);
-
-                        let arg_contents = args.as_ref().map(|a| &a.args);
-                        let mut amass_apply_macro = telety::v1::TY.apply(
-                            ty_macro_path.clone(),
-                            needle.clone(),
-                            quote_spanned!(span => {
-                                ::amass::__private::amass_apply!(#common <#arg_contents> #needle);
-                            }))
-                            .with_fallback(fallback);
-
-                        if let Some(telety_path) = telety.options().telety_path.as_ref() {
-                            amass_apply_macro =
-                                amass_apply_macro.with_telety_path(telety_path.clone());
+                    if syn_util::is_type_parameter(generics, &single_field.ty) {
+                        if variant_action == VariantAction::Force {
+                            return Err(syn::Error::new(
+                                type_path.span(),
+                                format!(
+                                    "Generic parameter `{}` cannot have deep impls",
+                                    type_path.to_token_stream(),
+                                ),
+                            ));
+                        }
+                        if let Some(leaf) =
+                            parameter_type(common, &telety, &mut apply_args_visitor, type_path)
+                        {
+                            if explicit_action.is_some()
+                                && common.is_type_parameter(&leaf.leaf_type)
+                            {
+                                explicit_parameters
+                                    .push((variant.span(), amass_variant.describe()));
+                            }
+                            reflection.amassed.push(Amassed {
+                                variant: amass_variant,
+                                leaf,
+                                prefer: variant_options.prefer,
+                            });
+                        }
+                    } else if let Some((wrapper, inner_path)) = Wrapper::from_type_path(type_path) {
+                        // `Box<T>`, `Rc<T>` and `Arc<T>` are converted through to `T`
+                        let wrapper_path = telety
+                            .alias_map()
+                            .get_alias(type_path)?
+                            .ok_or_else(|| unaliased_error(type_path))?
+                            .to_macro_path();
+                        if let Some(leaf) =
+                            amass_type(&telety, &mut apply_args_visitor, inner_path, nested)?
+                        {
+                            reflection.amassed.push(Amassed {
+                                variant: amass_variant.clone().with_wrapper(
+                                    wrapper,
+                                    wrapper_path.clone(),
+                                    true,
+                                ),
+                                leaf,
+                                prefer: variant_options.prefer,
+                            });
                         }
 
-                        generated_items.push(amass_apply_macro.into_token_stream());
+                        // The wrapper itself is still converted, but cannot be reflected
+                        let leaf = amass_type(&telety, &mut apply_args_visitor, type_path, None)?
+                            .ok_or_else(|| unaliased_error(type_path))?;
+                        reflection.amassed.push(Amassed {
+                            variant: amass_variant.with_wrapper(wrapper, wrapper_path, false),
+                            leaf,
+                            prefer: variant_options.prefer,
+                        });
+                    } else {
+                        let leaf = amass_type(&telety, &mut apply_args_visitor, type_path, nested)?
+                            .ok_or_else(|| unaliased_error(type_path))?;
+                        reflection.amassed.push(Amassed {
+                            variant: amass_variant,
+                            leaf,
+                            prefer: variant_options.prefer,
+                        });
                     }
-
-                    common.pop_variant();
                 } else if variant_action == VariantAction::Force {
                     return Err(syn::Error::new(
                         variant.fields.span(),
                         "Non-path types cannot have deep impls",
                     ));
+                } else if let Some(leaf) =
+                    non_path_type(&telety, &mut apply_args_visitor, &single_field.ty)
+                {
+                    reflection.amassed.push(Amassed {
+                        variant: amass_variant,
+                        leaf,
+                        prefer: variant_options.prefer,
+                    });
+                }
+            }
+
+            // Other source types are converted by the field's own `From` impls, inside any wrapper
+            if let Some(also_from) = &variant_options.also_from {
+                let converted = &reflection.amassed[converted..];
+                let field_variant = converted
+                    .iter()
+                    .find(|amassed| amassed.variant.through().is_some())
+                    .or_else(|| {
+                        converted
+                            .iter()
+                            .find(|amassed| !amassed.variant.is_wrapper_leaf())
+                    })
+                    .map(|amassed| amassed.variant.clone());
+                if let Some(field_variant) = field_variant {
+                    for ty in also_from {
+                        reflection.amassed.push(Amassed {
+                            variant: field_variant.clone().with_function(
+                                parse_quote_spanned!(ty.span() => ::core::convert::From::from),
+                            ),
+                            leaf: attribute_type(&telety, &mut apply_args_visitor, ty),
+                            prefer: variant_options.prefer,
+                        });
+                    }
                 }
             }
         }
     }
 
-    Ok(quote!(#(#generated_items)*))
+    if common.has_kind() {
+        reflection.kinds = variant_kinds(
+            &primary_path,
+            item_ident,
+            &variants,
+            is_struct,
+            &reflection.amassed,
+        );
+    }
+    if common.has_flat() {
+        reflection.flat = flat_variants(
+            common,
+            &primary_path,
+            item_ident,
+            &variants,
+            is_struct,
+            &reflection.amassed,
+        )?;
+    }
+
+    // `From<T> for Root<T>` overlaps with any other `From` impl for the root
+    if reflection.amassed.len() > 1 {
+        if let Some((span, variant)) = explicit_parameters.first() {
+            return Err(syn::Error::new(
+                *span,
+                format!(
+                    "`{variant}` cannot be converted into `{item_ident}`, as its type parameter \
+                    may be the type of another converted variant (remove the variant action to skip it)"
+                ),
+            ));
+        }
+        reflection
+            .amassed
+            .retain(|amassed| !common.is_type_parameter(&amassed.leaf.leaf_type));
+    }
+
+    Ok(reflection)
+}
+
+/// Variants which are reflected further are matched through their field,
+/// other variants are named after themselves, or after their enum if they have no fields.
+/// A struct only has a kind of its own if its field is reflected further.
+fn variant_kinds(
+    primary_path: &Path,
+    item_ident: &Ident,
+    variants: &[Variant],
+    is_struct: bool,
+    amassed: &[Amassed],
+) -> Vec<AmassNamed> {
+    let nested = amassed
+        .iter()
+        .filter(|amassed| amassed.leaf.nested.is_some());
+
+    if is_struct {
+        return nested
+            .map(|amassed| AmassNamed::new(item_ident.clone(), amassed.variant.clone(), None))
+            .collect();
+    }
+
+    let nested: Vec<_> = nested.collect();
+    variants
+        .iter()
+        .map(|variant| {
+            let amass_variant = nested
+                .iter()
+                .find(|amassed| amassed.variant.is_variant(&variant.ident))
+                .map_or_else(
+                    || AmassVariant::from_variant(primary_path, item_ident, variant),
+                    |amassed| amassed.variant.clone(),
+                );
+            AmassNamed::new(variant_name(item_ident, variant), amass_variant, None)
+        })
+        .collect()
+}
+
+/// Every variant must be converted, or have no fields, so no value is lost.
+/// A type without converted variants is not flattened, and stays whole in the variant which contains it.
+/// Values behind a `Box` are flattened, but shared values cannot be moved out of, so stay whole.
+fn flat_variants(
+    common: &AmassCommon,
+    primary_path: &Path,
+    item_ident: &Ident,
+    variants: &[Variant],
+    is_struct: bool,
+    amassed: &[Amassed],
+) -> syn::Result<Vec<AmassNamed>> {
+    if amassed.is_empty() && !common.variants().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let flattened = |ident: &Ident| {
+        let candidates: Vec<_> = amassed
+            .iter()
+            .filter(|amassed| {
+                (is_struct || amassed.variant.is_variant(ident))
+                    && matches!(amassed.variant.through(), None | Some(Wrapper::Box))
+            })
+            .collect();
+        // The `Box` itself is also converted, but its value is flattened instead.
+        // Fields built by a function cannot be flattened, so are only taken if there is nothing else.
+        let plain = || {
+            candidates
+                .iter()
+                .filter(|amassed| !amassed.variant.has_function())
+        };
+        plain()
+            .find(|amassed| amassed.variant.through().is_some())
+            .or_else(|| plain().next())
+            .or_else(|| candidates.first())
+            .copied()
+    };
+
+    let mut flat = vec![];
+    for variant in variants {
+        let ident = if is_struct {
+            item_ident
+        } else {
+            &variant.ident
+        };
+        match flattened(ident) {
+            Some(amassed) if amassed.variant.has_function() => {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    format!(
+                        "`{}` cannot be flattened, as it is built by a function",
+                        amassed.variant.describe(),
+                    ),
+                ));
+            }
+            Some(amassed) if amassed.variant.has_fills() => {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    format!(
+                        "`{}` cannot be flattened without losing its other fields",
+                        amassed.variant.describe(),
+                    ),
+                ));
+            }
+            Some(amassed) => flat.push(AmassNamed::new(
+                ident.clone(),
+                amassed.variant.clone(),
+                Some(amassed.leaf.leaf_type.clone()),
+            )),
+            None if variant.fields.is_empty() => flat.push(AmassNamed::new(
+                variant_name(item_ident, variant),
+                AmassVariant::from_variant(primary_path, item_ident, variant),
+                None,
+            )),
+            None => {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    format!(
+                        "`{item_ident}::{}` cannot be flattened \
+                        (only converted variants and variants without fields can be)",
+                        variant.ident,
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(flat)
+}
+
+/// The name of a variant in a generated enum.
+/// Variants without fields are qualified with their enum, e.g. `IPA::WestCoast` is `IpaWestCoast`.
+fn variant_name(item_ident: &Ident, variant: &Variant) -> Ident {
+    if variant.fields.is_empty() {
+        format_ident!("{}{}", camel_case(item_ident), variant.ident)
+    } else {
+        variant.ident.clone()
+    }
+}
+
+/// Acronyms are treated as words, e.g. `IPA` becomes `Ipa`
+fn camel_case(ident: &Ident) -> String {
+    let ident = ident.to_string();
+    if ident.chars().any(char::is_lowercase) {
+        ident
+    } else {
+        let mut chars = ident.chars();
+        chars
+            .next()
+            .into_iter()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect()
+    }
+}
+
+/// The field marked with `#[amass_field]` and its position, if any.
+/// Otherwise, if the other fields are all captured, the only field which is not.
+pub(crate) fn marked_field<'f>(
+    fields: &'f Fields,
+    captures: &[Capture],
+) -> syn::Result<Option<(usize, &'f Field)>> {
+    let mut marked = None;
+    for (index, field) in fields.iter().enumerate() {
+        for attr in &field.attrs {
+            if attr.path().is_ident("amass_field") {
+                attr.meta.require_path_only()?;
+                if marked.replace((index, field)).is_some() {
+                    return Err(syn::Error::new(
+                        attr.span(),
+                        "Only one field can be marked 'amass_field'",
+                    ));
+                }
+            }
+        }
+    }
+
+    if marked.is_none() && !captures.is_empty() {
+        let mut uncaptured = fields
+            .iter()
+            .enumerate()
+            .filter(|(_index, field)| !captures.iter().any(|capture| capture.matches(&field.ty)));
+        if let (Some(field), None) = (uncaptured.next(), uncaptured.next())
+            && fields.len() > 1
+        {
+            marked = Some(field);
+        }
+    }
+
+    Ok(marked)
+}
+
+/// The values of the fields other than the field at `index`,
+/// given by `#[amass_fill(expr)]`, the capture, or [Default::default]
+fn fill_fields(
+    fields: &Fields,
+    index: usize,
+    captures: &[Capture],
+) -> syn::Result<Punctuated<FieldValue, Token![,]>> {
+    let mut fills = Punctuated::new();
+    for (field_index, field) in fields.iter().enumerate() {
+        if field_index == index {
+            continue;
+        }
+
+        let mut fill = None;
+        for attr in &field.attrs {
+            if attr.path().is_ident("amass_fill") {
+                #[allow(
+                    clippy::collapsible_if,
+                    reason = "separate mutable and immutable clauses"
+                )]
+                if fill.replace(attr.parse_args::<Expr>()?).is_some() {
+                    return Err(syn::Error::new(
+                        attr.span(),
+                        "Only one 'amass_fill' attribute is allowed",
+                    ));
+                }
+            }
+        }
+
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(field_index)),
+        };
+        let capture = captures.iter().find(|capture| capture.matches(&field.ty));
+        let expr = fill.unwrap_or_else(|| match capture {
+            Some(capture) => capture.expr(field.ty.span()),
+            None => parse_quote_spanned!(field.ty.span() => ::core::default::Default::default()),
+        });
+        fills.push(parse_quote!(#member: #expr));
+    }
+    Ok(fills)
+}
+
+/// The leaf for a field whose type is a generic parameter, or [None] if no impl can be generated.
+/// The parameter is substituted with its argument, which is never reflected.
+fn parameter_type(
+    common: &AmassCommon,
+    telety: &telety::Telety,
+    apply_args_visitor: &mut ApplyGenericArguments,
+    type_path: &TypePath,
+) -> Option<Leaf> {
+    let mut ty = Type::Path(type_path.clone());
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        apply_args_visitor,
+        &mut ty,
+    );
+    // A parameter of the root can only be converted directly into the root
+    if common.is_type_parameter(&ty) && !common.variants().is_empty() {
+        return None;
+    }
+
+    let mut key = ty.clone();
+    QualifyPaths::new(&telety.options().module_path).visit_type_mut(&mut key);
+
+    Some(Leaf {
+        leaf_type: ty,
+        key,
+        nested: None,
+    })
+}
+
+/// The leaf for a tuple, array, reference, slice or parenthesized field type, which is never reflected.
+/// Returns [None] for other types.
+fn non_path_type(
+    telety: &telety::Telety,
+    apply_args_visitor: &mut ApplyGenericArguments,
+    ty: &Type,
+) -> Option<Leaf> {
+    if !matches!(
+        ty,
+        Type::Tuple(_) | Type::Array(_) | Type::Reference(_) | Type::Slice(_) | Type::Paren(_)
+    ) {
+        return None;
+    }
+
+    let mut key = ty.clone();
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        apply_args_visitor,
+        &mut key,
+    );
+    QualifyPaths::new(&telety.options().module_path).visit_type_mut(&mut key);
+
+    let mut leaf_type = ty.clone();
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        &mut telety.alias_map().visitor(),
+        &mut leaf_type,
+    );
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        apply_args_visitor,
+        &mut leaf_type,
+    );
+
+    Some(Leaf {
+        leaf_type,
+        key,
+        nested: None,
+    })
+}
+
+/// The path of a function given by `#[amass_action(with = ...)]`.
+/// Attributes are not aliased, so paths are qualified with the module of the type instead,
+/// relative to the crate being expanded as they are used, not only compared.
+fn qualify_function(telety: &telety::Telety, with: &Path) -> Path {
+    let module_path = telety.options().converted_containing_path();
+
+    // `Type::function` is qualified as `Type`, so e.g. `String::from` is left as it is
+    let mut function = TypePath {
+        qself: None,
+        path: with.clone(),
+    };
+    let name = (function.path.segments.len() > 1)
+        .then(|| function.path.segments.pop())
+        .flatten();
+    function.path.segments.pop_punct();
+    QualifyPaths::new(&module_path).visit_type_path_mut(&mut function);
+    function
+        .path
+        .segments
+        .extend(name.map(|pair| pair.into_value()));
+    function.path
+}
+
+/// The leaf for a type given in `#[amass_action(...)]`, which is never reflected.
+/// Like function paths, the type is qualified with the module of the type containing the variant.
+fn attribute_type(
+    telety: &telety::Telety,
+    apply_args_visitor: &mut ApplyGenericArguments,
+    ty: &Type,
+) -> Leaf {
+    let mut leaf_type = ty.clone();
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        apply_args_visitor,
+        &mut leaf_type,
+    );
+
+    let mut key = leaf_type.clone();
+    QualifyPaths::new(&telety.options().module_path).visit_type_mut(&mut key);
+    QualifyPaths::new(&telety.options().converted_containing_path()).visit_type_mut(&mut leaf_type);
+
+    Leaf {
+        leaf_type,
+        key,
+        nested: None,
+    }
+}
+
+/// telety does not alias some types, e.g. associated types of generic parameters (`T::Assoc`).
+/// `From<T::Assoc>` would overlap with `From<T> for T`, so no impl can be generated for them.
+fn unaliased_error(type_path: &TypePath) -> syn::Error {
+    syn::Error::new_spanned(
+        type_path,
+        "Field type cannot be converted, as it depends on a generic parameter \
+        (add `#[amass_action(ignore)]` to skip the variant)",
+    )
+}
+
+/// The leaf for a variant field type, or [None] if the type was not aliased.
+/// If `force` is provided, the type is also reflected.
+fn amass_type(
+    telety: &telety::Telety,
+    apply_args_visitor: &mut ApplyGenericArguments,
+    type_path: &TypePath,
+    force: Option<bool>,
+) -> syn::Result<Option<Leaf>> {
+    let Some(alias) = telety.alias_map().get_alias(type_path)? else {
+        return Ok(None);
+    };
+    let ty_macro_path = alias.to_macro_path();
+
+    let mut key = Type::Path(type_path.clone());
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        apply_args_visitor,
+        &mut key,
+    );
+    QualifyPaths::new(&telety.options().module_path).visit_type_mut(&mut key);
+
+    let mut args = alias.generic_arguments().cloned();
+    if let Some(args) = &mut args {
+        // Aliases may introduce generic parameters (e.g. `Self`), so they are substituted afterwards
+        directed_visit::visit_mut(
+            &mut directed_visit::syn::direct::FullDefault,
+            &mut telety.alias_map().visitor(),
+            args,
+        );
+        directed_visit::visit_mut(
+            &mut directed_visit::syn::direct::FullDefault,
+            apply_args_visitor,
+            args,
+        );
+    }
+
+    let nested = force.map(|force| {
+        let arg_contents = args.as_ref().map(|a| &a.args);
+        Nested {
+            force,
+            macro_path: ty_macro_path.clone(),
+            generic_arguments: parse_quote!(<#arg_contents>),
+        }
+    });
+
+    Ok(Some(Leaf {
+        leaf_type: parse_quote!(#ty_macro_path #args),
+        key,
+        nested,
+    }))
+}
+
+/// The fields of a struct as a variant. Helper attributes are taken from the field.
+fn struct_variant(item: &ItemStruct) -> Variant {
+    let mut fields = item.fields.iter();
+    let attrs = match (fields.next(), fields.next()) {
+        (Some(field), None) => field.attrs.clone(),
+        _ => vec![],
+    };
+
+    Variant {
+        attrs,
+        ident: item.ident.clone(),
+        fields: item.fields.clone(),
+        discriminant: None,
+    }
 }
@@ -0,0 +1,203 @@
+use proc_macro2::TokenStream;
+use quote::{ToTokens as _, format_ident, quote, quote_spanned};
+use syn::{
+    FnArg, Item, Pat, PatIdent, Path, Token, TraitItem, TraitItemFn, parse2,
+    punctuated::Punctuated, spanned::Spanned as _,
+};
+
+use crate::{
+    parse::{AmassDelegate, DelegateVariant},
+    syn_util,
+};
+
+pub(crate) fn amass_delegate(attr: TokenStream) -> syn::Result<TokenStream> {
+    let AmassDelegate {
+        trait_path,
+        parameters,
+        root_type,
+        variants,
+        reflected,
+        ..
+    } = parse2(attr)?;
+    let span = trait_path.span();
+
+    let reflected = reflected.ok_or_else(|| syn::Error::new(span, "Expected a reflected trait"))?;
+    let telety = telety::Telety::new(&reflected)?;
+    let Item::Trait(item_trait) = telety.item() else {
+        return Err(syn::Error::new(span, "Only traits can be delegated"));
+    };
+    if !item_trait.generics.params.is_empty() {
+        return Err(syn::Error::new(span, "Generic traits cannot be delegated"));
+    }
+
+    let mut methods = vec![];
+    for trait_item in &item_trait.items {
+        match trait_item {
+            TraitItem::Fn(item_fn) => {
+                if let Some(method) = delegate_fn(&telety, item_fn, &variants, &trait_path)? {
+                    methods.push(method);
+                }
+            }
+            TraitItem::Type(item_type) if item_type.default.is_none() => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Associated type `{}` cannot be delegated", item_type.ident),
+                ));
+            }
+            TraitItem::Const(item_const) if item_const.default.is_none() => {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "Associated constant `{}` cannot be delegated",
+                        item_const.ident
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    // Each field must implement the trait as well, which is reported at the field.
+    // Only fields which mention a parameter are bounded, as bounds on recursive types overflow,
+    // so the others are checked separately. Fields which mention `Self` are the root, which this implements.
+    // Bounds cannot be conditional, so bounded fields of conditional variants are checked by the arms instead.
+    let (bounded, checked): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .filter(|variant| !syn_util::mentions_self(variant.ty.to_token_stream()))
+        .partition(|variant| syn_util::mentions_parameter(&parameters, &variant.ty));
+    let bounds = bounded
+        .iter()
+        .filter(|variant| variant.cfgs.is_empty())
+        .map(|variant| {
+            let ty = &variant.ty;
+            syn_util::respan(quote!(#ty: #trait_path), ty.span())
+        });
+    let checks = checked.iter().map(|variant| {
+        let check = syn_util::check_bounds(&variant.ty, quote!(#trait_path));
+        let cfgs = &variant.cfgs;
+        quote!(#(#cfgs)* #check)
+    });
+    let (impl_generics, _type_generics, where_clause) = parameters.split_for_impl();
+    let predicates = where_clause.map(|w| &w.predicates);
+
+    Ok(quote_spanned!(span =>
+        #[allow(deprecated)]
+        impl #impl_generics #trait_path for #root_type
+        where
+            #predicates
+            #(#bounds,)*
+        {
+            #(#methods)*
+        }
+
+        #(#checks)*
+    ))
+}
+
+/// Forwards a method with a `self` receiver to the field of each variant.
+/// Methods without a receiver, or which use `Self` otherwise, cannot be forwarded, so must have a default.
+fn delegate_fn(
+    telety: &telety::Telety,
+    item_fn: &TraitItemFn,
+    variants: &Punctuated<DelegateVariant, Token![,]>,
+    trait_path: &Path,
+) -> syn::Result<Option<TokenStream>> {
+    let ident = &item_fn.sig.ident;
+    let span = trait_path.span();
+
+    let receiver = item_fn.sig.receiver();
+    let uses_self = item_fn
+        .sig
+        .inputs
+        .iter()
+        .filter(|arg| matches!(arg, FnArg::Typed(_)))
+        .any(|arg| syn_util::mentions_self(arg.to_token_stream()))
+        || syn_util::mentions_self(item_fn.sig.output.to_token_stream());
+
+    let receiver = match receiver {
+        Some(receiver) if receiver.colon_token.is_none() && !uses_self => receiver,
+        _ if item_fn.default.is_some() => return Ok(None),
+        Some(_) => {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "Method `{ident}` cannot be delegated, as it uses `Self` other than `self`"
+                ),
+            ));
+        }
+        None => {
+            return Err(syn::Error::new(
+                span,
+                format!("Function `{ident}` cannot be delegated, as it has no `self` receiver"),
+            ));
+        }
+    };
+
+    // Types in the signature are written relative to the trait's module
+    let mut sig = item_fn.sig.clone();
+    let mut alias_visitor = telety.alias_map().visitor();
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        &mut alias_visitor,
+        &mut sig.generics,
+    );
+    directed_visit::visit_mut(
+        &mut directed_visit::syn::direct::FullDefault,
+        &mut alias_visitor,
+        &mut sig.output,
+    );
+
+    let mut args = vec![];
+    for (index, arg) in sig.inputs.iter_mut().enumerate() {
+        if let FnArg::Typed(pat_type) = arg {
+            let arg_ident = format_ident!("arg_{index}", span = span);
+            *pat_type.pat = Pat::Ident(PatIdent {
+                attrs: vec![],
+                by_ref: None,
+                mutability: None,
+                ident: arg_ident.clone(),
+                subpat: None,
+            });
+            directed_visit::visit_mut(
+                &mut directed_visit::syn::direct::FullDefault,
+                &mut alias_visitor,
+                &mut *pat_type.ty,
+            );
+            args.push(arg_ident);
+        }
+    }
+    let arms = variants.iter().map(|variant| {
+        let DelegateVariant {
+            cfgs,
+            deref,
+            path,
+            member,
+            ty,
+            ..
+        } = variant;
+        let value = match (deref.is_some(), &receiver.reference, &receiver.mutability) {
+            (false, _, _) => quote_spanned!(span => value),
+            (true, None, _) => quote_spanned!(span => *value),
+            (true, Some(_), Some(_)) => quote_spanned!(span => &mut **value),
+            (true, Some(_), None) => quote_spanned!(span => &**value),
+        };
+        let mut call = quote_spanned!(span => <#ty as #trait_path>::#ident(#value, #(#args),*));
+        if sig.asyncness.is_some() {
+            call = quote_spanned!(span => #call.await);
+        }
+        if sig.unsafety.is_some() {
+            call = quote_spanned!(span => unsafe { #call });
+        }
+        quote_spanned!(span => #(#cfgs)* #path { #member: value, .. } => #call,)
+    });
+
+    // `self` must be the token from the signature to refer to the receiver
+    let self_token = &receiver.self_token;
+    Ok(Some(quote_spanned!(span =>
+        #sig {
+            match #self_token {
+                #(#arms)*
+            }
+        }
+    )))
+}
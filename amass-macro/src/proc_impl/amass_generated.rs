@@ -0,0 +1,35 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Ident,
+    parse::{Parse, ParseStream},
+    parse2,
+};
+
+use crate::parse;
+
+struct Args {
+    unique_ident: Ident,
+    ident: Ident,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            unique_ident: input.parse()?,
+            ident: input.parse()?,
+        })
+    }
+}
+
+/// Declares a generated enum where this is invoked, once the traversal has exported its macro
+pub(crate) fn amass_generated(attr: TokenStream) -> syn::Result<TokenStream> {
+    let Args {
+        unique_ident,
+        ident,
+    } = parse2(attr)?;
+
+    let macro_ident = parse::generated_macro(&unique_ident, &ident);
+
+    Ok(quote!(#macro_ident!{}))
+}
@@ -0,0 +1,59 @@
+use proc_macro2::TokenStream;
+use quote::quote_spanned;
+
+use super::traversal::AmassClaim;
+use crate::{
+    parse::{AmassCommon, AmassVariant, cfg_attrs},
+    syn_util,
+};
+
+/// A documented table of every resolved conversion into the root, as an associated const
+pub(crate) fn generate_conversions(common: &AmassCommon, claims: &[AmassClaim]) -> TokenStream {
+    let Some(ident) = &common.options().conversions else {
+        return TokenStream::new();
+    };
+    let span = ident.span();
+
+    let root_type = common.root_type();
+    let (impl_generics, _type_generics, where_clause) = common.parameters().split_for_impl();
+
+    let mut rows = vec![];
+    let mut conversions = vec![];
+    for claim in claims {
+        let leaf = syn_util::display_type(&claim.key);
+        let variants: Vec<_> = claim.variants.iter().map(AmassVariant::describe).collect();
+        let row = format!(
+            " | `{leaf}` | `{}` | {} |",
+            variants.join(" -> "),
+            variants.len()
+        );
+
+        // Rows of conditional variants are only documented where the variants exist
+        let cfgs: Vec<_> = cfg_attrs(&claim.variants)
+            .filter_map(|attr| attr.meta.require_list().ok())
+            .map(|list| &list.tokens)
+            .collect();
+        rows.push(if cfgs.is_empty() {
+            quote_spanned!(span => #[doc = #row])
+        } else {
+            quote_spanned!(span => #[cfg_attr(all(#(#cfgs),*), doc = #row)])
+        });
+
+        let cfgs = cfg_attrs(&claim.variants);
+        conversions.push(quote_spanned!(span =>
+            #(#cfgs)*
+            ::amass::Conversion::new(#leaf, &[#(#variants),*])
+        ));
+    }
+
+    quote_spanned!(span =>
+        impl #impl_generics #root_type #where_clause {
+            /// Every type which converts into this type, and the variants it is converted through.
+            ///
+            /// | Leaf | Variants | Depth |
+            /// |------|----------|-------|
+            #(#rows)*
+            pub const #ident: &'static [::amass::Conversion] = &[#(#conversions),*];
+        }
+    )
+}
@@ -0,0 +1,28 @@
+use proc_macro2::TokenStream;
+use quote::quote_spanned;
+
+use crate::parse::{AmassCommon, AmassFrom, cfg_attrs};
+
+/// A description of each generated impl, and the variants it goes through.
+pub(crate) fn generate_explain(common: &AmassCommon, amass_froms: &[AmassFrom]) -> TokenStream {
+    let Some(explain) = &common.options().explain else {
+        return TokenStream::new();
+    };
+    let span = explain.span();
+
+    let root_type = common.root_type();
+    let (impl_generics, _type_generics, where_clause) = common.parameters().split_for_impl();
+
+    let explained = amass_froms.iter().map(|amass_from| {
+        let cfgs = cfg_attrs(amass_from.common.variants());
+        let explanation = amass_from.explain();
+        quote_spanned!(span => #(#cfgs)* #explanation)
+    });
+
+    quote_spanned!(span =>
+        impl #impl_generics #root_type #where_clause {
+            /// Every impl generated for this type, and the variants it goes through.
+            pub const EXPLAIN: &'static [&'static str] = &[#(#explained),*];
+        }
+    )
+}
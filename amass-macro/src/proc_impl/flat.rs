@@ -0,0 +1,116 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{Ident, spanned::Spanned as _};
+
+use super::traversal::{AmassTraversal, check_names, named_match};
+use crate::parse::{AmassCommon, AmassVariant};
+
+/// The flat enum, with one variant for each leaf of the tree, and conversions to and from the root.
+pub(crate) fn generate_flat(
+    traversal: &AmassTraversal,
+    common: &AmassCommon,
+) -> syn::Result<TokenStream> {
+    let Some(ident) = &common.options().flat else {
+        return Ok(TokenStream::new());
+    };
+    let span = ident.span();
+
+    let flat: Vec<_> = traversal.flat.iter().collect();
+    check_names("Flat variant", &flat, span)?;
+
+    let root_type = common.root_type();
+
+    let flat_variants = flat.iter().map(|named| {
+        let variant_ident = Ident::new(&named.ident.to_string(), span);
+        let attrs = named.attrs();
+        match &named.leaf_type {
+            Some(leaf_type) => quote_spanned!(span => #attrs #variant_ident(#leaf_type)),
+            None => quote_spanned!(span => #attrs #variant_ident),
+        }
+    });
+
+    // Each leaf is converted like `From<Leaf> for Root`, and unit variants are nested as they are
+    let into_root = flat.iter().map(|named| {
+        let variant_ident = Ident::new(&named.ident.to_string(), span);
+        let cfgs = named.cfgs();
+        match &named.leaf_type {
+            Some(leaf_type) => {
+                let amass_from = named.amass_from(common, leaf_type);
+                // Bindings are spanned like those of the `From` impls
+                let value = quote_spanned!(leaf_type.span() => value);
+                let conversion = amass_from.nest(0..named.variants.len(), value.clone(), true);
+                quote_spanned!(span => #cfgs #ident::#variant_ident(#value) => #conversion,)
+            }
+            None => {
+                let amass_from = named.amass_from(common, root_type);
+                let unit = named.variants.last().map(AmassVariant::path);
+                let conversion = amass_from.nest(0..named.variants.len() - 1, quote!(#unit), true);
+                quote_spanned!(span => #cfgs #ident::#variant_ident => #conversion,)
+            }
+        }
+    });
+
+    let from_root = named_match(
+        span,
+        &flat,
+        0,
+        quote_spanned!(span => value),
+        true,
+        &|named| {
+            let variant_ident = Ident::new(&named.ident.to_string(), span);
+            match &named.leaf_type {
+                Some(leaf_type) => {
+                    let amass_from = named.amass_from(common, leaf_type);
+                    let last = named.variants.len() - 1;
+                    let value = quote_spanned!(leaf_type.span() => value);
+                    let pattern = amass_from.nest(last..last + 1, value, false);
+                    let mut leaf_value = amass_from.leaf_value();
+                    // Only `Box` is flattened through, as the value is moved out
+                    if named.variants[last].through().is_some() {
+                        leaf_value = quote_spanned!(leaf_type.span() => *#leaf_value);
+                    }
+                    (
+                        pattern,
+                        quote_spanned!(span => #ident::#variant_ident(#leaf_value)),
+                    )
+                }
+                None => {
+                    let pattern = named.variants.last().map(|v| v.kind_pattern(None));
+                    (
+                        quote!(#pattern),
+                        quote_spanned!(span => #ident::#variant_ident),
+                    )
+                }
+            }
+        },
+    );
+
+    let declaration = traversal.declare(
+        ident,
+        quote_spanned!(span =>
+            pub enum #ident {
+                #(#flat_variants,)*
+            }
+        ),
+    );
+
+    Ok(quote_spanned!(span =>
+        #declaration
+
+        #[allow(deprecated)]
+        impl ::core::convert::From<#ident> for #root_type {
+            fn from(value: #ident) -> Self {
+                match value {
+                    #(#into_root)*
+                }
+            }
+        }
+
+        #[allow(deprecated)]
+        impl ::core::convert::From<#root_type> for #ident {
+            fn from(value: #root_type) -> Self {
+                #from_root
+            }
+        }
+    ))
+}
@@ -0,0 +1,79 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::Ident;
+
+use super::traversal::{AmassTraversal, check_names, named_match};
+use crate::parse::AmassCommon;
+
+/// The kind enum, with one variant for each variant of the tree which is not nested further,
+/// and the `kind` method of the root.
+pub(crate) fn generate_kind(
+    traversal: &AmassTraversal,
+    common: &AmassCommon,
+) -> syn::Result<TokenStream> {
+    let Some(ident) = &common.options().kind else {
+        return Ok(TokenStream::new());
+    };
+    let span = ident.span();
+
+    let kinds: Vec<_> = traversal.kinds.iter().collect();
+    check_names("Kind", &kinds, span)?;
+
+    let root_type = common.root_type();
+    let (impl_generics, _type_generics, where_clause) = common.parameters().split_for_impl();
+
+    let kind_idents: Vec<_> = kinds
+        .iter()
+        .map(|k| Ident::new(&k.ident.to_string(), span))
+        .collect();
+    let kind_attrs = kinds.iter().map(|k| k.attrs());
+    let kind_cfgs = kinds.iter().map(|k| k.cfgs());
+    let body = named_match(
+        span,
+        &kinds,
+        0,
+        quote_spanned!(span => self),
+        false,
+        &|kind| {
+            let pattern = kind.variants.last().map(|v| v.kind_pattern(None));
+            let kind_ident = Ident::new(&kind.ident.to_string(), span);
+            (
+                quote!(#pattern),
+                quote_spanned!(span => #ident::#kind_ident),
+            )
+        },
+    );
+
+    let declaration = traversal.declare(
+        ident,
+        quote_spanned!(span =>
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub enum #ident {
+                #(#kind_attrs #kind_idents,)*
+            }
+        ),
+    );
+
+    Ok(quote_spanned!(span =>
+        #declaration
+
+        #[allow(deprecated)]
+        impl #ident {
+            /// Every kind, in declaration order
+            pub const ALL: &'static [Self] = &[#(#kind_cfgs Self::#kind_idents),*];
+
+            /// The position of this kind in [Self::ALL]
+            pub const fn index(self) -> usize {
+                self as usize
+            }
+        }
+
+        #[allow(deprecated)]
+        impl #impl_generics #root_type #where_clause {
+            /// The kind of the innermost variant of this value
+            pub fn kind(&self) -> #ident {
+                #body
+            }
+        }
+    ))
+}
@@ -6,3 +6,13 @@ mod amass_apply;
 pub(crate) use amass_apply::amass_apply;
 mod amass_from;
 pub(crate) use amass_from::amass_from;
+mod amass_delegate;
+pub(crate) use amass_delegate::amass_delegate;
+mod amass_generated;
+pub(crate) use amass_generated::amass_generated;
+mod conversions;
+mod explain;
+mod flat;
+mod kind;
+mod traversal;
+pub(crate) use traversal::AmassTraversal;
@@ -0,0 +1,703 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, quote, quote_spanned};
+use syn::{
+    AngleBracketedGenericArguments, Ident, LitBool, Path, PathArguments, Token, Type, bracketed,
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token::{Bracket, Paren},
+};
+
+use crate::{
+    on_conflict::OnConflict,
+    parse::{
+        AmassCommon, AmassFrom, AmassVariant, cfg_attrs, describe_type, describe_variants,
+        duplicate_error, generated_macro, is_conditional,
+    },
+    syn_util,
+};
+
+/// A leaf type found by a sequential traversal.
+/// Impls are only generated once the whole tree has been visited and conflicts are resolved.
+pub(crate) struct AmassClaim {
+    prefer: LitBool,
+    key_paren: Paren,
+    // (
+    pub key: Type,
+    // )
+    variants_bracket: Bracket,
+    // [
+    pub variants: Punctuated<AmassVariant, Token![,]>,
+    // ]
+    leaf_type_paren: Paren,
+    // (
+    leaf_type: Type,
+    // )
+}
+
+impl AmassClaim {
+    pub fn new(
+        prefer: bool,
+        key: Type,
+        variants: Punctuated<AmassVariant, Token![,]>,
+        leaf_type: Type,
+    ) -> Self {
+        Self {
+            prefer: LitBool::new(prefer, Span::call_site()),
+            key_paren: Default::default(),
+            key,
+            variants_bracket: Default::default(),
+            variants,
+            leaf_type_paren: Default::default(),
+            leaf_type,
+        }
+    }
+
+    /// Once the leaf type has been reflected, its canonical path replaces the path it was written with
+    pub fn set_canonical_path(&mut self, path: Path) {
+        let Type::Path(key) = &mut self.key else {
+            return;
+        };
+        let arguments = key
+            .path
+            .segments
+            .last()
+            .map(|s| s.arguments.clone())
+            .unwrap_or(PathArguments::None);
+        key.path = path;
+        if let Some(last) = key.path.segments.last_mut() {
+            last.arguments = arguments;
+        }
+    }
+
+    pub fn into_amass_from(self, mut common: AmassCommon) -> AmassFrom {
+        common.set_variants(self.variants);
+        AmassFrom {
+            leaf_name: common.leaf_name(&self.key),
+            common,
+            leaf_type: self.leaf_type,
+        }
+    }
+
+    fn key_string(&self) -> String {
+        self.key.to_token_stream().to_string()
+    }
+
+    fn describe_leaf(&self) -> String {
+        describe_type(&self.key)
+    }
+}
+
+impl Parse for AmassClaim {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key_content;
+        let variants_content;
+        let leaf_type_content;
+        Ok(Self {
+            prefer: input.parse()?,
+            key_paren: parenthesized!(key_content in input),
+            key: key_content.parse()?,
+            variants_bracket: bracketed!(variants_content in input),
+            variants: Punctuated::parse_terminated(&variants_content)?,
+            leaf_type_paren: parenthesized!(leaf_type_content in input),
+            leaf_type: leaf_type_content.parse()?,
+        })
+    }
+}
+
+impl ToTokens for AmassClaim {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            prefer,
+            key_paren,
+            key,
+            variants_bracket,
+            variants,
+            leaf_type_paren,
+            leaf_type,
+        } = self;
+        prefer.to_tokens(tokens);
+        key_paren.surround(tokens, |ts| key.to_tokens(ts));
+        variants_bracket.surround(tokens, |ts| variants.to_tokens(ts));
+        leaf_type_paren.surround(tokens, |ts| leaf_type.to_tokens(ts));
+    }
+}
+
+/// A nested type which has not been reflected yet
+pub(crate) struct AmassFrame {
+    pub force: LitBool,
+    variants_bracket: Bracket,
+    // [
+    pub variants: Punctuated<AmassVariant, Token![,]>,
+    // ]
+    macro_path_paren: Paren,
+    // (
+    pub macro_path: Path,
+    // )
+    pub generic_arguments: AngleBracketedGenericArguments,
+}
+
+impl AmassFrame {
+    pub fn new(
+        force: bool,
+        variants: Punctuated<AmassVariant, Token![,]>,
+        macro_path: Path,
+        generic_arguments: AngleBracketedGenericArguments,
+    ) -> Self {
+        Self {
+            force: LitBool::new(force, Span::call_site()),
+            variants_bracket: Default::default(),
+            variants,
+            macro_path_paren: Default::default(),
+            macro_path,
+            generic_arguments,
+        }
+    }
+}
+
+impl Parse for AmassFrame {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let variants_content;
+        let macro_path_content;
+        Ok(Self {
+            force: input.parse()?,
+            variants_bracket: bracketed!(variants_content in input),
+            variants: Punctuated::parse_terminated(&variants_content)?,
+            macro_path_paren: parenthesized!(macro_path_content in input),
+            macro_path: macro_path_content.parse()?,
+            generic_arguments: input.parse()?,
+        })
+    }
+}
+
+impl ToTokens for AmassFrame {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            force,
+            variants_bracket,
+            variants,
+            macro_path_paren,
+            macro_path,
+            generic_arguments,
+        } = self;
+        force.to_tokens(tokens);
+        variants_bracket.surround(tokens, |ts| variants.to_tokens(ts));
+        macro_path_paren.surround(tokens, |ts| macro_path.to_tokens(ts));
+        generic_arguments.to_tokens(tokens);
+    }
+}
+
+/// The state of a sequential traversal, passed from one reflected type to the next
+#[derive(Default)]
+pub(crate) struct AmassTraversal {
+    claims_bracket: Bracket,
+    // [
+    claims: Punctuated<AmassClaim, Token![,]>,
+    // ]
+    pending_bracket: Bracket,
+    // [
+    pending: Punctuated<AmassFrame, Token![,]>,
+    // ]
+    kinds_bracket: Bracket,
+    // [
+    pub kinds: Punctuated<AmassNamed, Token![,]>,
+    // ]
+    flat_bracket: Bracket,
+    // [
+    pub flat: Punctuated<AmassNamed, Token![,]>,
+    // ]
+    unique_ident_paren: Paren,
+    // (
+    /// Unique to the root within its crate, to name the macros which declare generated enums
+    unique_ident: Option<Ident>,
+    // )
+}
+
+impl AmassTraversal {
+    pub fn set_unique_ident(&mut self, unique_ident: Ident) {
+        self.unique_ident = Some(unique_ident);
+    }
+
+    /// Records the children of the type at `variants`.
+    /// Children are visited depth-first, so claims stay in declaration order.
+    pub fn reflect(
+        &mut self,
+        variants: &Punctuated<AmassVariant, Token![,]>,
+        canonical_path: Option<Path>,
+        children: Vec<(AmassClaim, Option<AmassFrame>)>,
+    ) {
+        let current = self
+            .claims
+            .iter()
+            .position(|c| c.variants.len() == variants.len() && is_prefix(&c.variants, variants));
+
+        let mut claims: Vec<_> = std::mem::take(&mut self.claims).into_iter().collect();
+        let insert_index = match current {
+            Some(index) => {
+                if let Some(path) = canonical_path {
+                    claims[index].set_canonical_path(path);
+                }
+                index + 1
+            }
+            None => claims.len(),
+        };
+        let inherit_prefer = current.is_some_and(|index| claims[index].prefer.value);
+
+        let mut frames = vec![];
+        let mut new_claims = vec![];
+        for (mut claim, frame) in children {
+            claim.prefer.value |= inherit_prefer;
+            new_claims.push(claim);
+            frames.extend(frame);
+        }
+        claims.splice(insert_index..insert_index, new_claims);
+        self.claims = claims.into_iter().collect();
+
+        let pending = std::mem::take(&mut self.pending);
+        self.pending = frames.into_iter().chain(pending).collect();
+    }
+
+    /// Replaces the kind of the variant at `variants` with the kinds of the variants of its type.
+    /// Types without variants of their own keep the kind of the variant which contains them.
+    pub fn reflect_kinds(
+        &mut self,
+        variants: &Punctuated<AmassVariant, Token![,]>,
+        children: Vec<AmassNamed>,
+    ) {
+        Self::replace_named(&mut self.kinds, variants, children);
+    }
+
+    /// Replaces the flat variant at `variants` with the flat variants of its type.
+    /// Types which are not flattened, or are not held by a flat variant, keep the flat variant which contains them.
+    pub fn reflect_flat(
+        &mut self,
+        variants: &Punctuated<AmassVariant, Token![,]>,
+        children: Vec<AmassNamed>,
+    ) {
+        Self::replace_named(&mut self.flat, variants, children);
+    }
+
+    fn replace_named(
+        named: &mut Punctuated<AmassNamed, Token![,]>,
+        variants: &Punctuated<AmassVariant, Token![,]>,
+        children: Vec<AmassNamed>,
+    ) {
+        if children.is_empty() {
+            return;
+        }
+
+        let mut entries: Vec<_> = std::mem::take(named).into_iter().collect();
+        let index = match entries
+            .iter()
+            .position(|n| n.variants.len() == variants.len() && is_prefix(&n.variants, variants))
+        {
+            Some(index) => {
+                entries.remove(index);
+                index
+            }
+            // Only the root is not contained in another variant
+            None if variants.is_empty() => entries.len(),
+            None => {
+                *named = entries.into_iter().collect();
+                return;
+            }
+        };
+        let children = children.into_iter().map(|mut child| {
+            let mut child_variants = variants.clone();
+            child_variants.extend(child.variants);
+            child.variants = child_variants;
+            child
+        });
+        entries.splice(index..index, children);
+        *named = entries.into_iter().collect();
+    }
+
+    /// Forgets the claim at `variants`, which will not generate an impl
+    pub fn remove_claim(&mut self, variants: &Punctuated<AmassVariant, Token![,]>) {
+        self.claims = std::mem::take(&mut self.claims)
+            .into_iter()
+            .filter(|c| c.variants.len() != variants.len() || !is_prefix(&c.variants, variants))
+            .collect();
+    }
+
+    pub fn pop_frame(&mut self) -> Option<AmassFrame> {
+        let mut pending = std::mem::take(&mut self.pending).into_iter();
+        let frame = pending.next();
+        self.pending = pending.collect();
+        frame
+    }
+
+    /// Resolves conflicting claims, returning the claims which should generate impls.
+    /// Without a conflict resolution, conflicting claims are all returned.
+    pub fn resolve(&mut self, on_conflict: Option<OnConflict>) -> syn::Result<Vec<AmassClaim>> {
+        let claims: Vec<_> = std::mem::take(&mut self.claims).into_iter().collect();
+        let keys: Vec<_> = claims.iter().map(AmassClaim::key_string).collect();
+        let Some(on_conflict) = on_conflict else {
+            Self::check_duplicates(&claims, &keys)?;
+            return Ok(claims);
+        };
+
+        let mut resolved = vec![false; claims.len()];
+        let mut winners = vec![false; claims.len()];
+        let mut error: Option<syn::Error> = None;
+
+        for index in 0..claims.len() {
+            if resolved[index] {
+                continue;
+            }
+
+            // Claims nested under a losing claim cannot be reached, so they do not compete
+            let has_lost_ancestor = |i: usize| {
+                (0..claims.len()).any(|j| {
+                    resolved[j]
+                        && !winners[j]
+                        && claims[j].variants.len() < claims[i].variants.len()
+                        && is_prefix(&claims[j].variants, &claims[i].variants)
+                })
+            };
+            let group: Vec<_> = (index..claims.len())
+                .filter(|&i| keys[i] == keys[index] && !has_lost_ancestor(i))
+                .collect();
+            for &i in &group {
+                resolved[i] = true;
+            }
+            let Some(&first) = group.first() else {
+                continue;
+            };
+
+            let preferred: Vec<_> = group
+                .iter()
+                .copied()
+                .filter(|&i| claims[i].prefer.value)
+                .collect();
+            let winner = if group.len() == 1 {
+                Some(first)
+            } else {
+                match (on_conflict, preferred.as_slice()) {
+                    (OnConflict::First, []) => Some(first),
+                    (OnConflict::First, [preferred, ..]) | (OnConflict::Prefer, [preferred]) => {
+                        Some(*preferred)
+                    }
+                    _ => None,
+                }
+            };
+
+            match winner {
+                Some(winner) => winners[winner] = true,
+                None => {
+                    let e = Self::conflict_error(on_conflict, &claims, &group, &preferred);
+                    match &mut error {
+                        Some(error) => error.combine(e),
+                        None => error = Some(e),
+                    }
+                }
+            }
+        }
+
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        Ok(claims
+            .into_iter()
+            .zip(winners)
+            .filter_map(|(claim, winner)| winner.then_some(claim))
+            .collect())
+    }
+
+    /// Without conflict resolution, every claim generates impls, so each leaf must be claimed once
+    fn check_duplicates(claims: &[AmassClaim], keys: &[String]) -> syn::Result<()> {
+        let mut error: Option<syn::Error> = None;
+        for (index, claim) in claims.iter().enumerate() {
+            // Each leaf is reported once, for its first two claims
+            if keys[..index].contains(&keys[index]) || is_conditional(&claim.variants) {
+                continue;
+            }
+            if let Some(other) = (index + 1..claims.len())
+                .find(|&i| keys[i] == keys[index] && !is_conditional(&claims[i].variants))
+            {
+                let e = duplicate_error(&claim.key, &claim.variants, &claims[other].variants);
+                match &mut error {
+                    Some(error) => error.combine(e),
+                    None => error = Some(e),
+                }
+            }
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    fn conflict_error(
+        on_conflict: OnConflict,
+        claims: &[AmassClaim],
+        group: &[usize],
+        preferred: &[usize],
+    ) -> syn::Error {
+        // Name the claims which could not be decided between
+        let (a, b) = match preferred {
+            [a, b, ..] => (*a, *b),
+            _ => (group[0], group[1]),
+        };
+        let (a, b) = (&claims[a], &claims[b]);
+
+        let hint = match on_conflict {
+            OnConflict::Error => "",
+            _ if preferred.len() > 1 => {
+                " (only one variant may be marked with `#[amass_action(prefer)]`)"
+            }
+            _ => " (mark one variant with `#[amass_action(prefer)]`)",
+        };
+
+        syn::Error::new(
+            b.variants
+                .first()
+                .map_or_else(Span::call_site, AmassVariant::span),
+            format!(
+                "`{}` is reachable through both `{}` and `{}`{hint}",
+                a.describe_leaf(),
+                describe_variants(&a.variants),
+                describe_variants(&b.variants),
+            ),
+        )
+    }
+}
+
+impl Parse for AmassTraversal {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let claims_content;
+        let pending_content;
+        let kinds_content;
+        let flat_content;
+        let unique_ident_content;
+        Ok(Self {
+            claims_bracket: bracketed!(claims_content in input),
+            claims: Punctuated::parse_terminated(&claims_content)?,
+            pending_bracket: bracketed!(pending_content in input),
+            pending: Punctuated::parse_terminated(&pending_content)?,
+            kinds_bracket: bracketed!(kinds_content in input),
+            kinds: Punctuated::parse_terminated(&kinds_content)?,
+            flat_bracket: bracketed!(flat_content in input),
+            flat: Punctuated::parse_terminated(&flat_content)?,
+            unique_ident_paren: parenthesized!(unique_ident_content in input),
+            unique_ident: unique_ident_content.parse()?,
+        })
+    }
+}
+
+impl ToTokens for AmassTraversal {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            claims_bracket,
+            claims,
+            pending_bracket,
+            pending,
+            kinds_bracket,
+            kinds,
+            flat_bracket,
+            flat,
+            unique_ident_paren,
+            unique_ident,
+        } = self;
+        claims_bracket.surround(tokens, |ts| claims.to_tokens(ts));
+        pending_bracket.surround(tokens, |ts| pending.to_tokens(ts));
+        kinds_bracket.surround(tokens, |ts| kinds.to_tokens(ts));
+        flat_bracket.surround(tokens, |ts| flat.to_tokens(ts));
+        unique_ident_paren.surround(tokens, |ts| unique_ident.to_tokens(ts));
+    }
+}
+
+/// A variant which is not nested further, named in a generated enum.
+/// In the flat enum, the variant holds the leaf, unless the variant has no fields.
+pub(crate) struct AmassNamed {
+    pub ident: Ident,
+    variants_bracket: Bracket,
+    // [
+    pub variants: Punctuated<AmassVariant, Token![,]>,
+    // ]
+    leaf_type_paren: Paren,
+    // (
+    pub leaf_type: Option<Type>,
+    // )
+}
+
+impl AmassNamed {
+    /// `variant` of a reflected type, which is prefixed with the variants leading to the type
+    pub fn new(ident: Ident, variant: AmassVariant, leaf_type: Option<Type>) -> Self {
+        let mut variants = Punctuated::new();
+        variants.push(variant);
+        Self {
+            ident,
+            variants_bracket: Default::default(),
+            variants,
+            leaf_type_paren: Default::default(),
+            leaf_type,
+        }
+    }
+
+    /// The attributes of a generated variant, which exists where the whole chain does,
+    /// and is deprecated if any variant in the chain is
+    pub fn attrs(&self) -> TokenStream {
+        let cfgs = cfg_attrs(&self.variants);
+        let deprecated = self.variants.iter().find_map(AmassVariant::deprecated);
+        quote!(#(#cfgs)* #deprecated)
+    }
+
+    pub fn cfgs(&self) -> TokenStream {
+        let cfgs = cfg_attrs(&self.variants);
+        quote!(#(#cfgs)*)
+    }
+
+    // The last variant of the chain as a leaf of the root
+    pub fn amass_from(&self, common: &AmassCommon, leaf_type: &Type) -> AmassFrom {
+        let mut common = common.clone();
+        common.set_variants(self.variants.clone());
+        AmassFrom {
+            common,
+            leaf_type: leaf_type.clone(),
+            leaf_name: None,
+        }
+    }
+}
+
+impl Parse for AmassNamed {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let variants_content;
+        let leaf_type_content;
+        Ok(Self {
+            ident: input.parse()?,
+            variants_bracket: bracketed!(variants_content in input),
+            variants: Punctuated::parse_terminated(&variants_content)?,
+            leaf_type_paren: parenthesized!(leaf_type_content in input),
+            leaf_type: if leaf_type_content.is_empty() {
+                None
+            } else {
+                Some(leaf_type_content.parse()?)
+            },
+        })
+    }
+}
+
+impl ToTokens for AmassNamed {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            ident,
+            variants_bracket,
+            variants,
+            leaf_type_paren,
+            leaf_type,
+        } = self;
+        ident.to_tokens(tokens);
+        variants_bracket.surround(tokens, |ts| variants.to_tokens(ts));
+        leaf_type_paren.surround(tokens, |ts| leaf_type.to_tokens(ts));
+    }
+}
+
+impl AmassTraversal {
+    /// Declares a generated enum through an exported macro, which the root invokes in a module beside itself,
+    /// as items in the blocks of the traversal cannot be named.
+    pub fn declare(&self, ident: &Ident, declaration: TokenStream) -> TokenStream {
+        let unique_ident = self
+            .unique_ident
+            .as_ref()
+            .expect("The root is reflected first");
+        let macro_ident = generated_macro(unique_ident, ident);
+        // Tokens reflected from other expansions would carry their hygiene into the exported macro,
+        // which the incremental cache fails to restore
+        let declaration = syn_util::respan(declaration, ident.span());
+        quote_spanned!(ident.span() =>
+            #[doc(hidden)]
+            #[macro_export]
+            #[allow(non_local_definitions, clippy::crate_in_macro_def)]
+            macro_rules! #macro_ident {
+                () => {
+                    #declaration
+                };
+            }
+        )
+    }
+
+    /// Empty generated enums, declared when the traversal fails,
+    /// so the root still finds them and only the failure is reported
+    pub fn generate_placeholders(&self, common: &AmassCommon) -> TokenStream {
+        let options = common.options();
+        options
+            .kind
+            .iter()
+            .chain(&options.flat)
+            .map(|ident| self.declare(ident, quote_spanned!(ident.span() => pub enum #ident {})))
+            .collect()
+    }
+}
+
+pub(crate) fn check_names(what: &str, named: &[&AmassNamed], span: Span) -> syn::Result<()> {
+    let mut error: Option<syn::Error> = None;
+    for (index, entry) in named.iter().enumerate() {
+        if let Some(other) = named[..index].iter().find(|n| n.ident == entry.ident) {
+            let e = syn::Error::new(
+                span,
+                format!(
+                    "{what} `{}` would be generated for both `{}` and `{}`",
+                    entry.ident,
+                    describe_variants(&other.variants),
+                    describe_variants(&entry.variants),
+                ),
+            );
+            match &mut error {
+                Some(error) => error.combine(e),
+                None => error = Some(e),
+            }
+        }
+    }
+    error.map_or(Ok(()), Err)
+}
+
+// Matches `scrutinee` against the variants at `depth`, giving the arm of each named variant with `leaf`.
+// Named variants are in depth-first order, so those sharing a variant are adjacent.
+// If `owned`, `scrutinee` is moved out of, which is only possible through a `Box`.
+pub(crate) fn named_match(
+    span: Span,
+    named: &[&AmassNamed],
+    depth: usize,
+    scrutinee: TokenStream,
+    owned: bool,
+    leaf: &dyn Fn(&AmassNamed) -> (TokenStream, TokenStream),
+) -> TokenStream {
+    let mut arms = vec![];
+    let mut start = 0;
+    while let Some(entry) = named.get(start) {
+        let variant = &entry.variants[depth];
+        let end = named[start..]
+            .iter()
+            .position(|n| !n.variants[depth].is_same(variant))
+            .map_or(named.len(), |len| start + len);
+        let group = &named[start..end];
+
+        let cfgs = variant.cfg_attrs();
+        if entry.variants.len() == depth + 1 {
+            let (pattern, value) = leaf(entry);
+            arms.push(quote_spanned!(span => #(#cfgs)* #pattern => #value,));
+        } else {
+            let pattern = variant.kind_pattern(Some(quote_spanned!(span => value)));
+            let inner = match (variant.through().is_some(), owned) {
+                (true, true) => quote_spanned!(span => *value),
+                (true, false) => quote_spanned!(span => &**value),
+                (false, _) => quote_spanned!(span => value),
+            };
+            let nested = named_match(span, group, depth + 1, inner, owned, leaf);
+            arms.push(quote_spanned!(span => #(#cfgs)* #pattern => #nested,));
+        }
+        start = end;
+    }
+
+    quote_spanned!(span =>
+        match #scrutinee {
+            #(#arms)*
+        }
+    )
+}
+
+fn is_prefix(
+    prefix: &Punctuated<AmassVariant, Token![,]>,
+    variants: &Punctuated<AmassVariant, Token![,]>,
+) -> bool {
+    prefix.len() <= variants.len() && prefix.iter().zip(variants).all(|(a, b)| a.is_same(b))
+}
@@ -1,8 +1,39 @@
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{ToTokens as _, quote};
 use syn::{
-    Expr, ExprPath, GenericArgument, GenericParam, Generics, Path, PathArguments, PathSegment,
-    Token, Type, TypePath, punctuated::Punctuated,
+    Expr, ExprPath, GenericArgument, GenericParam, Generics, Ident, Lifetime, Path, PathArguments,
+    PathSegment, Token, Type, TypePath,
+    punctuated::Punctuated,
+    spanned::Spanned as _,
+    visit::Visit,
+    visit_mut::{self, VisitMut},
 };
 
+/// Sets an option which may only be given once
+pub(crate) fn set_once<T>(slot: &mut Option<T>, value: T, span: Span) -> syn::Result<()> {
+    if slot.replace(value).is_some() {
+        return Err(syn::Error::new(span, "parameter appears multiple times"));
+    }
+    Ok(())
+}
+
+/// Sets the span of every token, so diagnostics point at a single location
+pub(crate) fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(group) = &tt {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                tt = TokenTree::Group(respanned);
+            } else {
+                tt.set_span(span);
+            }
+            tt
+        })
+        .collect()
+}
+
 // Copied from telety for now
 pub(crate) fn generic_params_to_arguments(
     params: &Generics,
@@ -51,3 +82,172 @@ pub(crate) fn generic_param_to_argument(param: &GenericParam) -> GenericArgument
         }
     }
 }
+
+/// Whether the type is one of the type parameters, written on its own (`T`, but not `Vec<T>`)
+pub(crate) fn is_type_parameter(generics: &Generics, ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    type_path.qself.is_none()
+        && type_path.path.get_ident().is_some_and(|ident| {
+            generics
+                .type_params()
+                .any(|type_param| &type_param.ident == ident)
+        })
+}
+
+/// Whether the type mentions any of the generic parameters (`Vec<T>` or `&'a str`, but not `Vec<u8>`)
+pub(crate) fn mentions_parameter(generics: &Generics, ty: &Type) -> bool {
+    struct MentionsParameter<'g> {
+        generics: &'g Generics,
+        found: bool,
+    }
+
+    impl<'ast> Visit<'ast> for MentionsParameter<'_> {
+        fn visit_ident(&mut self, ident: &'ast Ident) {
+            self.found |= self.generics.type_params().any(|p| &p.ident == ident)
+                || self.generics.const_params().any(|p| &p.ident == ident);
+        }
+
+        fn visit_lifetime(&mut self, lifetime: &'ast Lifetime) {
+            self.found |= self
+                .generics
+                .lifetimes()
+                .any(|p| p.lifetime.ident == lifetime.ident);
+        }
+    }
+
+    let mut visitor = MentionsParameter {
+        generics,
+        found: false,
+    };
+    visitor.visit_type(ty);
+    visitor.found
+}
+
+/// Whether the tokens mention `Self`
+pub(crate) fn mentions_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => ident == "Self",
+        TokenTree::Group(group) => mentions_self(group.stream()),
+        _ => false,
+    })
+}
+
+/// Checks that the type implements `bounds`, reported at the type, without bounding an impl by it.
+/// Bounds on concrete types overflow when the type contains the implementing type.
+pub(crate) fn check_bounds(ty: &Type, bounds: TokenStream) -> TokenStream {
+    respan(
+        quote! {
+            const _: fn() = || {
+                fn check<T: ?::core::marker::Sized + #bounds>() {}
+                check::<#ty>();
+            };
+        },
+        ty.span(),
+    )
+}
+
+// Assumed to never be shadowed, so they are the same type in every module
+const GLOBAL_TYPES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64", "String", "Vec", "Box", "Option", "Result",
+];
+const GLOBAL_CRATES: &[&str] = &["std", "core", "alloc"];
+
+/// Makes a best-effort module-independent path for each type path written in `module_path`,
+/// so types written in different modules can be compared.
+pub(crate) struct QualifyPaths<'p> {
+    module_path: &'p Path,
+}
+
+impl<'p> QualifyPaths<'p> {
+    /// `module_path` must be global, e.g. `::my_crate::my_mod`
+    pub fn new(module_path: &'p Path) -> Self {
+        Self { module_path }
+    }
+
+    fn qualify(&self, path: &mut Path) {
+        if path.leading_colon.is_some() {
+            return;
+        }
+        let Some(first) = path.segments.first() else {
+            return;
+        };
+        let first = first.ident.to_string();
+        if GLOBAL_CRATES.contains(&first.as_str())
+            || (path.segments.len() == 1 && GLOBAL_TYPES.contains(&first.as_str()))
+        {
+            return;
+        }
+
+        let mut qualified = self.module_path.clone();
+        let mut segments = path.segments.iter().cloned().peekable();
+        match first.as_str() {
+            "crate" => {
+                while qualified.segments.len() > 1 {
+                    qualified.segments.pop();
+                }
+                segments.next();
+            }
+            "self" => {
+                segments.next();
+            }
+            _ => {
+                while segments.next_if(|s| s.ident == "super").is_some() {
+                    qualified.segments.pop();
+                }
+            }
+        }
+        qualified.segments.extend(segments);
+
+        *path = qualified;
+    }
+}
+
+impl VisitMut for QualifyPaths<'_> {
+    fn visit_type_path_mut(&mut self, i: &mut TypePath) {
+        visit_mut::visit_type_path_mut(self, i);
+        if i.qself.is_none() {
+            self.qualify(&mut i.path);
+        }
+    }
+}
+
+/// Writes a type for diagnostics, without the paths leading to each type,
+/// e.g. `Wrap<Stout>` for `::my_crate::Wrap<::my_crate::Stout>`
+pub(crate) fn display_type(ty: &Type) -> String {
+    let mut ty = ty.clone();
+    UnqualifyPaths.visit_type_mut(&mut ty);
+
+    let mut display = ty.to_token_stream().to_string();
+    for (from, to) in [
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        (" ;", ";"),
+        ("& ", "&"),
+        ("( ", "("),
+        (" )", ")"),
+        ("[ ", "["),
+        (" ]", "]"),
+    ] {
+        display = display.replace(from, to);
+    }
+    display
+}
+
+struct UnqualifyPaths;
+
+impl VisitMut for UnqualifyPaths {
+    fn visit_type_path_mut(&mut self, i: &mut TypePath) {
+        visit_mut::visit_type_path_mut(self, i);
+        if i.qself.is_some() {
+            return;
+        }
+        if let Some(last) = i.path.segments.pop() {
+            i.path = Path::from(last.into_value());
+        }
+    }
+}
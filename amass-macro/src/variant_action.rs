@@ -1,10 +1,15 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
 use syn::{
-    Attribute, Ident,
+    Attribute, Expr, Fields, Ident, LitStr, Meta, MetaNameValue, Path, Token, Type, Variant,
     parse::{Parse, ParseStream},
     parse2,
+    punctuated::Punctuated,
     spanned::Spanned as _,
 };
 
+use crate::syn_util;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub(crate) enum VariantAction {
     Ignore,
@@ -12,49 +17,224 @@ pub(crate) enum VariantAction {
     #[default]
     Deep,
     Force,
+    Tuple,
 }
 
 impl VariantAction {
-    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Option<Self>> {
-        let mut action = None;
+    pub fn from_ident(ident: &Ident) -> syn::Result<Self> {
+        match ident.to_string().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "shallow" => Ok(Self::Shallow),
+            "deep" => Ok(Self::Deep),
+            "force" => Ok(Self::Force),
+            "tuple" => Ok(Self::Tuple),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("Invalid variant action '{ident}'"),
+            )),
+        }
+    }
+}
+
+impl Parse for VariantAction {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        Self::from_ident(&ident)
+    }
+}
+
+/// The arguments of the `#[amass_action(...)]` helper attribute
+#[derive(Debug, Default)]
+pub(crate) struct VariantOptions {
+    pub action: Option<VariantAction>,
+    pub prefer: bool,
+    /// Written before the variant by the generated `Display`
+    pub prefix: Option<LitStr>,
+    /// The function which builds the field from the `from` type
+    pub with: Option<(Path, Type)>,
+    /// Other types converted into the field by its own `From` impls
+    pub also_from: Option<Punctuated<Type, Token![,]>>,
+}
+
+impl VariantOptions {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = None;
         for attr in attrs {
             if attr.path().is_ident("amass_action") {
                 #[allow(
                     clippy::collapsible_if,
                     reason = "separate mutable and immutable clauses"
                 )]
-                if action
+                if options
                     .replace(parse2(attr.meta.require_list()?.tokens.clone())?)
                     .is_some()
                 {
                     return Err(syn::Error::new(
                         attr.span(),
-                        "Only one 'amass' attribute is allowed",
+                        "Only one 'amass_action' attribute is allowed",
                     ));
                 }
             }
         }
 
-        Ok(action)
+        Ok(options.unwrap_or_default())
     }
+}
 
-    pub fn from_ident(ident: &Ident) -> syn::Result<Self> {
-        match ident.to_string().as_str() {
-            "ignore" => Ok(Self::Ignore),
-            "shallow" => Ok(Self::Shallow),
-            "deep" => Ok(Self::Deep),
-            "force" => Ok(Self::Force),
-            _ => Err(syn::Error::new(
-                ident.span(),
-                format!("Invalid variant action '{ident}'"),
-            )),
+impl Parse for VariantOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let args = Punctuated::<Meta, Token![,]>::parse_terminated_with(input, Self::parse_arg)?;
+
+        let mut options = Self::default();
+        let mut prefer = None;
+        let mut with = None;
+        let mut from = None;
+        for arg in &args {
+            match arg {
+                Meta::Path(path) if path.is_ident("prefer") => {
+                    syn_util::set_once(&mut prefer, (), arg.span())?;
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("prefix") => {
+                    let prefix = parse2(name_value.value.to_token_stream())?;
+                    syn_util::set_once(&mut options.prefix, prefix, arg.span())?;
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("with") => {
+                    let path: Path = parse2(name_value.value.to_token_stream())?;
+                    syn_util::set_once(&mut with, path, arg.span())?;
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("from") => {
+                    let ty: Type = parse2(name_value.value.to_token_stream())?;
+                    syn_util::set_once(&mut from, ty, arg.span())?;
+                }
+                Meta::List(list) if list.path.is_ident("also_from") => {
+                    let types = list.parse_args_with(Punctuated::parse_terminated)?;
+                    syn_util::set_once(&mut options.also_from, types, arg.span())?;
+                }
+                Meta::Path(path) => {
+                    let action = VariantAction::from_ident(path.require_ident()?)?;
+                    if options.action.replace(action).is_some() {
+                        return Err(syn::Error::new(
+                            arg.span(),
+                            "Only one variant action is allowed",
+                        ));
+                    }
+                }
+                _ => return Err(syn::Error::new(arg.span(), "Invalid parameter")),
+            }
         }
+
+        options.prefer = prefer.is_some();
+        options.with = match (with, from) {
+            (Some(with), Some(from)) => Some((with, from)),
+            (None, None) => None,
+            (Some(with), None) => {
+                return Err(syn::Error::new(
+                    with.span(),
+                    "`with` requires the type converted from (e.g. `from = Type`)",
+                ));
+            }
+            (None, Some(from)) => {
+                return Err(syn::Error::new(
+                    from.span(),
+                    "`from` requires a conversion function (e.g. `with = Type::new`)",
+                ));
+            }
+        };
+
+        Ok(options)
     }
 }
 
-impl Parse for VariantAction {
+impl VariantOptions {
+    // Types such as `Vec<u8>` are not expressions, which `Meta` would require
+    fn parse_arg(input: ParseStream) -> syn::Result<Meta> {
+        let fork = input.fork();
+        if fork.parse::<Ident>().is_ok_and(|ident| ident == "from") && fork.peek(Token![=]) {
+            let ident: Ident = input.parse()?;
+            let eq_token = input.parse()?;
+            let ty: Type = input.parse()?;
+
+            Ok(Meta::NameValue(MetaNameValue {
+                path: Path::from(ident),
+                eq_token,
+                value: Expr::Verbatim(ty.into_token_stream()),
+            }))
+        } else {
+            input.parse()
+        }
+    }
+}
+
+/// Replaces the action of matching variants anywhere in the tree.
+/// `Enum::Variant = action` matches a variant of an enum by name,
+/// `Type = action` matches any single field variant whose field type has that name.
+#[derive(Clone)]
+pub(crate) struct ActionOverride {
+    path: Path,
+    eq: Token![=],
+    action_ident: Ident,
+    action: VariantAction,
+}
+
+impl ActionOverride {
+    pub fn action(&self) -> VariantAction {
+        self.action
+    }
+
+    pub fn matches_variant(&self, enum_ident: &Ident, variant: &Variant) -> bool {
+        let mut segments = self.path.segments.iter().rev();
+        match (segments.next(), segments.next()) {
+            (Some(variant_segment), Some(enum_segment)) => {
+                enum_segment.ident == *enum_ident && variant_segment.ident == variant.ident
+            }
+            _ => false,
+        }
+    }
+
+    pub fn matches_type(&self, variant: &Variant) -> bool {
+        if self.path.segments.len() != 1 {
+            return false;
+        }
+
+        let mut fields = match &variant.fields {
+            Fields::Named(fields) => fields.named.iter(),
+            Fields::Unnamed(fields) => fields.unnamed.iter(),
+            Fields::Unit => return false,
+        };
+        let (Some(field), None) = (fields.next(), fields.next()) else {
+            return false;
+        };
+
+        if let Type::Path(type_path) = &field.ty
+            && let Some(last) = type_path.path.segments.last()
+        {
+            self.path.segments[0].ident == last.ident
+        } else {
+            false
+        }
+    }
+}
+
+impl Parse for ActionOverride {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let ident: Ident = input.parse()?;
-        Self::from_ident(&ident)
+        let path = Path::parse_mod_style(input)?;
+        let eq = input.parse()?;
+        let action_ident: Ident = input.parse()?;
+        let action = VariantAction::from_ident(&action_ident)?;
+
+        Ok(Self {
+            path,
+            eq,
+            action_ident,
+            action,
+        })
+    }
+}
+
+impl ToTokens for ActionOverride {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.path.to_tokens(tokens);
+        self.eq.to_tokens(tokens);
+        self.action_ident.to_tokens(tokens);
     }
 }
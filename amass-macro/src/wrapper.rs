@@ -0,0 +1,93 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{
+    GenericArgument, Ident, PathArguments, Type, TypePath,
+    parse::{Parse, ParseStream},
+};
+
+/// A smart pointer which amass converts through, allocating the inner value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Wrapper {
+    Box,
+    Rc,
+    Arc,
+}
+
+impl Wrapper {
+    /// If `ty` is written as a wrapper, e.g. `Box<T>`, returns the wrapper and `T`.
+    /// Wrappers are recognized by name only.
+    pub fn from_type_path(ty: &TypePath) -> Option<(Self, &TypePath)> {
+        if ty.qself.is_some() {
+            return None;
+        }
+
+        let last = ty.path.segments.last()?;
+        let wrapper = match last.ident.to_string().as_str() {
+            "Box" => Self::Box,
+            "Rc" => Self::Rc,
+            "Arc" => Self::Arc,
+            _ => return None,
+        };
+
+        let PathArguments::AngleBracketed(args) = &last.arguments else {
+            return None;
+        };
+        let mut args = args.args.iter();
+        let (Some(GenericArgument::Type(Type::Path(inner))), None) = (args.next(), args.next())
+        else {
+            return None;
+        };
+
+        Some((wrapper, inner))
+    }
+}
+
+impl Parse for Wrapper {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(WrapperParse::parse(input)?.wrapper)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WrapperParse {
+    wrapper: Wrapper,
+    span: Span,
+}
+
+impl WrapperParse {
+    pub fn new(wrapper: Wrapper, span: Span) -> Self {
+        Self { wrapper, span }
+    }
+
+    pub fn wrapper(&self) -> Wrapper {
+        self.wrapper
+    }
+}
+
+impl Parse for WrapperParse {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+
+        let ident: Ident = input.parse()?;
+        let wrapper = match ident.to_string().as_str() {
+            "Box" => Ok(Wrapper::Box),
+            "Rc" => Ok(Wrapper::Rc),
+            "Arc" => Ok(Wrapper::Arc),
+            _ => Err(syn::Error::new_spanned(ident, "Unknown wrapper type")),
+        }?;
+
+        Ok(Self { wrapper, span })
+    }
+}
+
+impl ToTokens for WrapperParse {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let s = match self.wrapper {
+            Wrapper::Box => "Box",
+            Wrapper::Rc => "Rc",
+            Wrapper::Arc => "Arc",
+        };
+        let ident = Ident::new(s, self.span);
+        ident.to_tokens(tokens);
+    }
+}
@@ -73,6 +73,8 @@
 //!   for the the field types contained within that type.
 //! * force - A From impl will be created for the field type and for the the field types contained within that type.
 //!   If the type is not telety-enabled, a compile error will be generated.
+//! * tuple - For variants with any number of fields, a From impl will be created for a tuple of the field types,
+//!   e.g. `From<(i32, i32)>` for `Point(i32, i32)`. Tuples are never nested further, and `AsLeaf` is not implemented for them.
 //!
 //! A default action can be specified on the main attribute: `#[amass(default = force)]`.
 //! If no default is provided on the attribute, `deep` is the default action.  
@@ -102,33 +104,133 @@
 //! # fn main() { }
 //! ```
 //!
-//! Note that is not currently possible to override behavior on an upstream enum.  
-//! In this example, two impls for `From<DiamondTop> for DiamondBottom` are generated, causing a compile error.  
+//! Variants of one enum with the same type are reported with an error pointing at both variants.
+//! ```rust,compile_fail
+//! # use amass::amass_telety;
+//! #[amass_telety(crate)]
+//! pub enum Number {
+//!     Small(i32),
+//!     Large(i32),
+//! }
+//!
+//! # fn main() { }
+//! ```
+//! ```text
+//! error: `i32` is converted through both `Number::Small` and `Number::Large` (use `#[amass_action(ignore)]` or `#[amass_action(shallow)]` on one of them)
+//! error: `i32` is also converted through `Number::Small`
+//! ```
+//!
+//! When a type is reachable through multiple paths, an impl is generated for each path.
+//! Duplicates across nested subtrees are only detected when the whole tree is traversed at once
+//! (with `on_conflict`, `kind`, `flat`, `conversions` or `explain`), and are then reported like those of one enum.
+//! By default, each enum is reflected separately, so two subtrees which both contain a type are not detected,
+//! and the compiler reports the conflicting impls (E0119) instead.
+//! In this example, two impls for `From<DiamondTop> for DiamondBottom` are generated, causing a compile error.
 //! ```rust,compile_fail,E0119
 //! # use amass::amass_telety;
 //!
 //! pub struct DiamondTop;
 //!
 //! #[amass_telety(crate)]
-//! pub struct DiamondLeft {
+//! pub enum DiamondLeft {
 //!     Top(DiamondTop)
 //! }
 //!
 //! #[amass_telety(crate)]
-//! pub struct DiamondRight {
+//! pub enum DiamondRight {
 //!     Top(DiamondTop)
 //! }
 //!
 //! #[amass_telety(crate)]
-//! pub struct DiamondBottom {
+//! pub enum DiamondBottom {
+//!     Left(DiamondLeft),
+//!     Right(DiamondRight),
+//! }
+//! # fn main() { }
+//! ```
+//! To solve this, either `DiamondLeft` or `DiamondRight` can `ignore` the `Top` variant,
+//! or `DiamondBottom` can use `shallow` for the `Left` or `Right` variant.  
+//! If the upstream enums cannot be changed, the root enum can override the action of variants anywhere in the tree:
+//! `override(Enum::Variant = action)` applies to a single variant, and `override(Type = action)` applies to
+//! every single-field variant of that type. Types and variants are matched by name only.
+//! Overrides take precedence over any `#[amass_action(...)]` or `default`.
+//! ```rust
+//! # use amass::amass_telety;
+//! # pub struct DiamondTop;
+//! # #[amass_telety(crate)]
+//! # pub enum DiamondLeft {
+//! #     Top(DiamondTop)
+//! # }
+//! # #[amass_telety(crate)]
+//! # pub enum DiamondRight {
+//! #     Top(DiamondTop)
+//! # }
+//! // Only skips the `DiamondTop` -> `DiamondLeft` -> `DiamondBottom` impl
+//! #[amass_telety(crate, override(DiamondLeft::Top = ignore))]
+//! pub enum DiamondBottom {
+//!     Left(DiamondLeft),
+//!     Right(DiamondRight),
+//! }
+//! # fn main() { }
+//! ```
+//!
+//! Alternatively, the root enum can resolve conflicts automatically with `on_conflict = ...`:
+//! * first - The first path to reach the type (depth-first, in declaration order) is used.
+//! * prefer - The path through a variant marked `#[amass_action(prefer)]` is used.
+//!   It is an error if no path, or more than one path, is preferred.
+//! * error - Any type reachable through multiple paths is an error.
+//!
+//! Marking a variant of the root enum as `prefer` implies `on_conflict = prefer`.  
+//! A nested variant marked `prefer` is an error unless the root resolves conflicts, as it would be ignored.
+//! `first` also uses a preferred path if there is one.
+//! ```rust
+//! # use amass::amass_telety;
+//! # pub struct DiamondTop;
+//! # #[amass_telety(crate)]
+//! # pub enum DiamondLeft {
+//! #     Top(DiamondTop)
+//! # }
+//! # #[amass_telety(crate)]
+//! # pub enum DiamondRight {
+//! #     Top(DiamondTop)
+//! # }
+//! #[amass_telety(crate)]
+//! pub enum DiamondBottom {
+//!     Left(DiamondLeft),
+//!     // `DiamondTop` -> `DiamondRight` -> `DiamondBottom`
+//!     #[amass_action(prefer)]
+//!     Right(DiamondRight),
+//! }
+//! # fn main() { }
+//! ```
+//! ```rust,compile_fail
+//! # use amass::amass_telety;
+//! # pub struct DiamondTop;
+//! # #[amass_telety(crate)]
+//! # pub enum DiamondLeft {
+//! #     Top(DiamondTop)
+//! # }
+//! # #[amass_telety(crate)]
+//! # pub enum DiamondRight {
+//! #     Top(DiamondTop)
+//! # }
+//! // error: `DiamondTop` is reachable through both `DiamondBottom::Left -> DiamondLeft::Top`
+//! // and `DiamondBottom::Right -> DiamondRight::Top`
+//! #[amass_telety(crate, on_conflict = error)]
+//! pub enum DiamondBottom {
 //!     Left(DiamondLeft),
 //!     Right(DiamondRight),
 //! }
 //! # fn main() { }
 //! ```
-//! To solve this, either `DiamondLeft` or `DiamondRight` must `ignore` the `Top` variant,
-//! or `DiamondBottom` must use `shallow` for the `Left` or `Right` variant. It is not possible to *only* skip
-//! the `DiamondTop` -> `DiamondLeft`/`DiamondRight` -> `DiamondBottom` impl.
+//! Resolving conflicts requires visiting every nested enum before generating any impls,
+//! which uses more of the macro recursion limit. Very large trees may need to raise `#![recursion_limit]`.  
+//! Types are compared by path. Types which are not telety-enabled are compared by the path they are written with,
+//! so the same type written through different `use` imports may not be detected.
+//!
+//! ## Further options
+//! The generated impls and items, such as `impls`, `kind`, `flat` and `path`, are configured on [amass].
+//! `path` is opt-in, as an inherent `amass_path` method generated on every amassed type could clash with the type's own `amass_path`.
 
 /// Generate [From] impls recursively for single-field variant types.  
 /// ```rust
@@ -161,7 +263,7 @@
 ///
 /// ## Variant actions
 /// amass works on variants which have a single field, whether that field is named (`Variant { field: i32 }`)
-/// or unnamed (`Variant(i32)`). Other variants are ignored.  
+/// or unnamed (`Variant(i32)`). Other variants are ignored unless marked `tuple`, or one of their fields is marked `#[amass_field]`.  
 /// amass has customizable behavior for applicable fields. The following options exist:  
 /// * ignore - No From impl will be created for the field type or the field types contained within that type.
 /// * shallow - A From impl will be created for the field type, but not for any field types contained within that type.
@@ -169,6 +271,12 @@
 ///   for the the field types contained within that type.
 /// * force - A From impl will be created for the field type and for the the field types contained within that type.
 ///   If the type is not telety-enabled, a compile error will be generated.
+/// * tuple - For variants with any number of fields, a From impl will be created for a tuple of the field types,
+///   e.g. `From<(i32, i32)>` for `Point(i32, i32)`. Tuples are never nested further, and `AsLeaf` is not implemented for them.
+///   
+/// Tuple, array, reference, slice and parenthesized field types (`Pair((u8, u8))`, `Name(&'a str)`) cannot contain
+/// further variants, so they are converted as `shallow`, even when marked `deep`. Marking such a variant `force` is an error,
+/// as is a `force` default.
 ///   
 /// A default action can be specified on the main attribute: `#[amass(default = force)]`.
 /// If no default is provided on the attribute, `deep` is the default action.  
@@ -198,20 +306,707 @@
 /// # fn main() { }
 /// ```
 ///
+/// `max_depth = N` stops nesting once a conversion passes through `N` variants, as if the remaining variants
+/// were `shallow`. `#[amass_action(force)]` on a variant at the maximum depth is an error.
+/// Large trees of telety-enabled types can generate many impls, so limiting the depth can reduce compile times.
+///
+/// ## Structs
+/// A struct with a single field (a newtype) can also be amassed. It is treated like an enum with a single variant,
+/// so conversions pass through it to the root. `#[amass_action(...)]` can be placed on the field.
+/// The field must be visible to the root enum.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct InnerError;
+///
+/// #[amass_telety(crate)]
+/// pub struct ApiError(pub InnerError);
+///
+/// #[amass_telety(crate)]
+/// pub enum Error {
+///     Api(ApiError),
+/// }
+///
+/// fn main() {
+///     let _: ApiError = InnerError.into();
+///     let _: Error = InnerError.into();
+/// }
+/// ```
+///
+/// ## Extra fields
+/// A variant with several fields can be converted through one of them by marking it with `#[amass_field]`.
+/// The other fields are filled with `Default::default()`, or with the expression given by `#[amass_fill(...)]`.
+/// The expression is evaluated in the impls of every enum containing the variant, so it should only use
+/// absolute paths.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct IoError;
+///
+/// #[amass_telety(crate)]
+/// pub enum Error {
+///     Io {
+///         #[amass_field]
+///         source: IoError,
+///         #[amass_fill(3)]
+///         retries: u32,
+///         context: Option<String>,
+///     },
+/// }
+///
+/// fn main() {
+///     let _: Error = IoError.into();
+/// }
+/// ```
+///
+/// ## Capture
+/// `capture = location` fills fields of type `&'static core::panic::Location<'static>` with
+/// [`Location::caller`](core::panic::Location::caller), and marks the generated `From::from` as `#[track_caller]`,
+/// so `?` records where an error was converted. `capture = backtrace` fills fields of type
+/// [`std::backtrace::Backtrace`](std::backtrace::Backtrace) with `Backtrace::capture()`.  
+/// Imports cannot be followed, so the field type must be written with its full path (`std::panic::Location` also works).
+/// Other types with the same name are converted or filled like any other field.  
+/// A variant whose other fields are all captured is converted through its remaining field, without `#[amass_field]`.
+/// Fields are filled at every level of the root's tree, but nested enums need the option too for their own impls.
+/// A nested enum's own option also applies to its fields when the root converts through them.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct IoError;
+///
+/// #[amass_telety(crate, capture = location)]
+/// pub enum Error {
+///     Io(IoError, &'static core::panic::Location<'static>),
+/// }
+///
+/// fn read() -> Result<(), Error> {
+///     Err(IoError)?
+/// }
+///
+/// fn main() {
+///     let Err(Error::Io(_, location)) = read() else { unreachable!() };
+///     assert_eq!(location.file(), file!());
+/// }
+/// ```
+///
+/// ## Wrappers
+/// Variant fields of type `Box<T>`, `Rc<T>` or `Arc<T>` are converted through: impls are generated for `T`
+/// (and the types nested in `T`), allocating the wrapper during the conversion.
+/// The wrapper type itself is still converted as a `shallow` variant. Wrappers are recognized by name.  
+/// If `T` is the type itself or an enclosing type (e.g. `Neg(Box<Expr>)` in `Expr`), only the wrapper is converted.  
+/// `TryFrom` returns the original value if an `Rc` or `Arc` is shared, and `AsLeaf::as_leaf_mut` returns `None`.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct Leaf;
+///
+/// #[amass_telety(crate)]
+/// pub enum Inner {
+///     Leaf(Leaf),
+/// }
+///
+/// #[amass_telety(crate)]
+/// pub enum Outer {
+///     Inner(Box<Inner>),
+/// }
+///
+/// fn main() {
+///     let _: Outer = Leaf.into();
+///     let _: Outer = Inner::Leaf(Leaf).into();
+///     let _: Outer = Box::new(Inner::Leaf(Leaf)).into();
+/// }
+/// ```
+///
+/// ## Conversion functions
+/// `#[amass_action(with = path::function, from = Type)]` converts a variant from `Type`, building its field
+/// by calling the function. The impls are generated at every level of the root's tree like any other,
+/// but `Type` is not reflected further, and the field type itself is not converted.  
+/// As the function cannot be reversed, `TryFrom` and `AsLeaf` are not generated, and the variant cannot
+/// be flattened. The paths are qualified with the module given to `#[telety(...)]`.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct IoContext {
+///     pub error: std::io::Error,
+///     pub retries: u32,
+/// }
+///
+/// impl IoContext {
+///     pub fn new(error: std::io::Error) -> Self {
+///         Self { error, retries: 3 }
+///     }
+/// }
+///
+/// #[amass_telety(crate)]
+/// pub enum FetchError {
+///     #[amass_action(with = IoContext::new, from = std::io::Error)]
+///     Io(IoContext),
+/// }
+///
+/// #[amass_telety(crate)]
+/// pub enum Error {
+///     Fetch(FetchError),
+/// }
+///
+/// fn main() {
+///     let _: Error = std::io::Error::other("disk full").into();
+/// }
+/// ```
+///
+/// `#[amass_action(also_from(TypeA, TypeB))]` also converts the variant from each type, through the field's own
+/// `From` impls (inside any wrapper), in addition to converting the field type as usual.
+/// ```rust
+/// # use amass::amass_telety;
+/// use std::num::{ParseFloatError, ParseIntError};
+///
+/// pub struct ParseError;
+///
+/// impl From<ParseIntError> for ParseError {
+///     fn from(_: ParseIntError) -> Self {
+///         Self
+///     }
+/// }
+///
+/// impl From<ParseFloatError> for ParseError {
+///     fn from(_: ParseFloatError) -> Self {
+///         Self
+///     }
+/// }
+///
+/// #[amass_telety(crate)]
+/// pub enum Error {
+///     #[amass_action(also_from(ParseIntError, ParseFloatError))]
+///     Parse(ParseError),
+/// }
+///
+/// fn parse(s: &str) -> Result<u32, Error> {
+///     Ok(s.parse()?)
+/// }
+/// # fn main() { }
+/// ```
+///
+/// ## Recursion
+/// A type which contains itself (e.g. through a `Box`) would generate impls endlessly, so variants which lead back
+/// to an enclosing type are skipped. With `on_cycle = error`, amass instead reports an error naming the path
+/// through which the type is reached again, unless it is reached directly through a wrapper (see [Wrappers](#wrappers)).
+/// Only the option on the root is used.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct Literal;
+///
+/// #[amass_telety(crate)]
+/// pub enum Expr {
+///     Literal(Literal),
+///     Block(Box<Block>),
+/// }
+///
+/// #[amass_telety(crate)]
+/// pub enum Block {
+///     Expr(Expr),
+/// }
+///
+/// fn main() {
+///     let _: Expr = Literal.into();
+///     let _: Block = Literal.into();
+///     let _: Expr = Block::Expr(Expr::Literal(Literal)).into();
+/// }
+/// ```
+///
+/// ## Impls
+/// By default, amass only generates `From` impls. The `impls` option selects which impls are generated
+/// for each (root, leaf) pair:
+/// * From - `From<Leaf> for Root`
+/// * TryFrom - `TryFrom<Root> for Leaf`. If the root does not contain a `Leaf`, the original value is returned as the error.
+/// * AsLeaf - [`AsLeaf<Leaf>`](AsLeaf) for `Root`, to borrow the `Leaf` without consuming the root.
+/// * Leaf - [`Leaf<Root>`](Leaf) for `Leaf`, to bound generic code on the types which amass converts into the root.
+///
+/// Only the options of the root enum are used; the `impls` of nested enums apply when they are amassed themselves.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct A;
+/// pub struct B;
+///
+/// #[amass_telety(crate, impls(From, TryFrom))]
+/// pub enum AB {
+///     A(A),
+///     B(B),
+/// }
+///
+/// fn main() {
+///     let ab: AB = A.into();
+///     assert!(A::try_from(ab).is_ok());
+///     let ab: AB = B.into();
+///     assert!(matches!(A::try_from(ab), Err(AB::B(B))));
+/// }
+/// ```
+///
+/// ## Variant path
+/// `path` generates an inherent `fn amass_path(&self) -> AmassPath` method,
+/// which names the variants of the value, continuing into fields of types which also use `path`. See [AmassPath].  
+/// It is opt-in, as an inherent method generated on every amassed type could clash with the type's own `amass_path`.  
+/// Whether a field is followed is decided where the type is declared, so fields whose type is a generic parameter
+/// are never followed, even if the parameter is a type which uses `path`.
+///
+/// ## Kind
+/// `kind = BeerKind` generates a fieldless `Copy` enum with one variant for each variant which is not nested further,
+/// named after the variant, or after its enum and the variant if it has no fields (`IPA::WestCoast` is `IpaWestCoast`).
+/// The root gets a `fn kind(&self) -> BeerKind` method, and the kind enum has `BeerKind::ALL`,
+/// in declaration order, and `fn index(self) -> usize`, its position in `ALL`.
+/// The kind enum is declared beside the root, with the root's visibility.
+/// Two variants with the same kind name are an error.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct Pilsner;
+/// pub struct Stout;
+///
+/// #[amass_telety(crate)]
+/// pub enum IPA {
+///     WestCoast,
+///     NewEngland,
+/// }
+///
+/// #[amass_telety(crate, kind = BeerKind)]
+/// pub enum Beer {
+///     Pilsner(Pilsner),
+///     Stout(Stout),
+///     IPA(IPA),
+/// }
+///
+/// fn main() {
+///     assert_eq!(Beer::from(IPA::NewEngland).kind(), BeerKind::IpaNewEngland);
+///     assert_eq!(BeerKind::ALL.len(), 4);
+///     assert_eq!(BeerKind::Stout.index(), 1);
+/// }
+/// ```
+///
+/// ## Flat
+/// `flat = FlatBeer` generates a single-level enum with one variant for each leaf, named after the variant which holds it,
+/// with `From<Beer> for FlatBeer` and `From<FlatBeer> for Beer`. Variants without fields are kept, named as for `kind`.
+/// Like the kind enum, it is declared beside the root, with the root's visibility.
+/// Every variant of a flattened type must be converted, or have no fields, so no value is lost.
+/// Types without converted variants are kept whole, as are values behind an `Rc` or `Arc`, which cannot be moved out of.
+/// Generic types cannot be flattened.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct Pilsner;
+/// pub struct Stout;
+///
+/// #[amass_telety(crate)]
+/// pub enum Ale {
+///     Stout(Stout),
+///     Cask,
+/// }
+///
+/// #[amass_telety(crate, flat = FlatBeer)]
+/// pub enum Beer {
+///     Pilsner(Pilsner),
+///     Ale(Box<Ale>),
+/// }
+///
+/// fn main() {
+///     let flat = FlatBeer::from(Beer::from(Stout));
+///     assert!(matches!(flat, FlatBeer::Stout(Stout)));
+///     let beer = Beer::from(FlatBeer::AleCask);
+///     assert!(matches!(beer, Beer::Ale(ale) if matches!(*ale, Ale::Cask)));
+/// }
+/// ```
+///
+/// ## Conversions
+/// `conversions = CONVERSIONS` generates an associated const on the root, listing every leaf which converts into it
+/// as a [`Conversion`], depth-first in declaration order. Its documentation is a table of the leaf type,
+/// the variants it is converted through and its depth, so the root's API docs show what it can be converted from.
+/// Conversions through a `#[cfg(...)]` variant are only listed where the variant exists.
+///
+/// ## Delegate
+/// `delegate(path::Trait)` implements a trait for the root by forwarding each method to the field of the variant,
+/// so the delegating `match` does not need to be written at every level.
+/// The trait must have `#[telety(...)]`, and the field type of every variant (or the field marked `#[amass_field]`)
+/// must implement it. Fields in a `Box`, `Rc` or `Arc` are forwarded to the value inside.
+/// Methods without a `self` receiver, or which use `Self` otherwise, cannot be forwarded, so must have a default.
+/// ```rust
+/// # use amass::amass_telety;
+/// # use telety::telety;
+/// #[telety(crate)]
+/// pub trait Diagnostic {
+///     fn code(&self) -> u32;
+/// }
+///
+/// pub struct NotFound;
+///
+/// impl Diagnostic for NotFound {
+///     fn code(&self) -> u32 {
+///         404
+///     }
+/// }
+///
+/// #[amass_telety(crate, delegate(Diagnostic))]
+/// pub enum HttpError {
+///     NotFound(NotFound),
+/// }
+///
+/// #[amass_telety(crate, delegate(Diagnostic))]
+/// pub enum Error {
+///     Http(HttpError),
+/// }
+///
+/// fn main() {
+///     assert_eq!(Error::from(NotFound).code(), 404);
+/// }
+/// ```
+///
+/// ## Error
+/// `error` implements `Display` and `std::error::Error` for the root, so error enums need no wrappers at each level.
+/// A variant with a single field (or a field marked `#[amass_field]`) displays the field, after the prefix
+/// given by `#[amass_action(prefix = "...")]` if any, and the field is its `source`.
+/// Other variants display their prefix, or their name, and have no `source`.
+/// ```rust
+/// # use amass::amass_telety;
+/// #[derive(Debug)]
+/// pub struct NotFound;
+///
+/// impl std::fmt::Display for NotFound {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str("not found")
+///     }
+/// }
+///
+/// impl std::error::Error for NotFound {}
+///
+/// #[amass_telety(crate, error)]
+/// #[derive(Debug)]
+/// pub enum Error {
+///     #[amass_action(prefix = "loading config")]
+///     NotFound(NotFound),
+///     Cancelled,
+/// }
+///
+/// fn main() {
+///     use std::error::Error as _;
+///
+///     let error = Error::from(NotFound);
+///     assert_eq!(error.to_string(), "loading config: not found");
+///     assert!(error.source().is_some());
+///     assert_eq!(Error::Cancelled.to_string(), "Cancelled");
+/// }
+/// ```
+///
+/// ## Conditional and deprecated variants
+/// `#[cfg(...)]` on a variant applies to everything generated for it, in every enum containing it,
+/// so the impls only exist where the variant does. Field types are aliased regardless, so must always exist.  
+/// The predicates are evaluated in the crate declaring the root, so a `feature` of an upstream crate's enum
+/// is checked against the root crate's feature of the same name, which must match it.  
+/// Conversions through a `#[deprecated]` variant of a nested type warn where the root is declared, naming the variant,
+/// as trait impls cannot be deprecated themselves. The root's own deprecated variants convert without a warning.
+/// Generated `kind` and `flat` variants are deprecated along with the variants they match.
+/// ```rust,compile_fail
+/// #![deny(deprecated)]
+/// # use amass::amass_telety;
+/// pub struct OldError;
+/// pub struct TlsError;
+///
+/// #[amass_telety(crate)]
+/// pub enum NetError {
+///     #[deprecated(note = "no longer returned")]
+///     Old(OldError),
+///     #[cfg(feature = "tls")]
+///     Tls(TlsError),
+/// }
+///
+/// // `From<OldError> for Error` warns that it goes through `NetError::Old`
+/// #[amass_telety(crate)]
+/// pub enum Error {
+///     Net(NetError),
+/// }
+/// # fn main() { }
+/// ```
+///
+/// ## Explain
+/// `explain` lists each generated conversion and the variants it goes through in an associated const,
+/// `EXPLAIN`, so they can be checked without expanding the macros.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct Stout;
+///
+/// #[amass_telety(crate)]
+/// pub enum Ale {
+///     Stout(Stout),
+/// }
+///
+/// #[amass_telety(crate, impls(From, TryFrom), explain)]
+/// pub enum Beer {
+///     Ale(Ale),
+/// }
+///
+/// # fn main() {
+/// assert_eq!(Beer::EXPLAIN, [
+///     "`From<Ale> for Beer`, `TryFrom<Beer> for Ale` via `Beer::Ale`",
+///     "`From<Stout> for Beer`, `TryFrom<Beer> for Stout` via `Beer::Ale -> Ale::Stout`",
+/// ]);
+/// # }
+/// ```
+///
 /// ## Limitations
 /// enums using amass are subject to [telety's limitations](https://docs.rs/telety/latest/telety/#limitations).  
 /// Just as if the `From` impls were written manually, multiple impls for the same type are not allowed.
 /// You must ensure that if a type appears in multiple variant fields in the same enum 'tree' that at most one impl
-/// is generated for it. You can use the `ignore` or `shallow` variant actions to do so.
+/// is generated for it. You can use the `ignore` or `shallow` variant actions to do so, or if the variants belong
+/// to an upstream enum, the `override(Enum::Variant = action)` or `override(Type = action)` options.
+/// The `on_conflict = first | prefer | error` option resolves or reports such conflicts automatically,
+/// using the `#[amass_action(prefer)]` variant attribute to choose between paths.  
+/// A field whose type is a generic parameter (`Inner(T)`) is always `shallow`, and `force` is an error.
+/// `From<T> for Root<T>` would overlap with any other `From` impl, so it is only generated when no other variant
+/// of the root is converted, and `TryFrom` is never generated for it. Otherwise the variant is skipped at the root,
+/// unless it has an explicit variant action, which is an error. A parameter given a concrete argument (`Wrap<Stout>`)
+/// is converted normally.  
+/// An associated type of a generic parameter (`Inner(T::Item)`) can never be converted, as `From<T::Item>` overlaps
+/// with `From<T> for T`. Such variants are an error unless they are `ignore`d.
 pub use amass_macro::amass;
 /// Like `#[telety(...)]` followed by `#[amass(...)]`, but does not require the `telety` crate as a direct dependency.  
 /// The containing module path must the first argument to the attribute, e.g. `#[amass_telety(crate::my_mod, default = shallow)]`.
 pub use amass_macro::amass_telety;
 
+/// Borrow a value of type `T` nested anywhere within an amassed enum.  
+/// Implemented for each (root, leaf) pair when `AsLeaf` is included in the `impls` option of [amass].
+/// ```rust
+/// # use amass::{AsLeaf, amass_telety};
+/// pub struct Stout;
+///
+/// #[amass_telety(crate, impls(From, AsLeaf))]
+/// pub enum Ale {
+///     Stout(Stout),
+/// }
+///
+/// #[amass_telety(crate, impls(From, AsLeaf))]
+/// pub enum Beer {
+///     Ale(Ale),
+/// }
+///
+/// fn main() {
+///     let beer: Beer = Stout.into();
+///     let stout: Option<&Stout> = beer.as_leaf();
+///     assert!(stout.is_some());
+///     assert!(AsLeaf::<Ale>::as_leaf(&beer).is_some());
+/// }
+/// ```
+pub trait AsLeaf<T> {
+    /// Returns a reference to the nested `T`, if `self` contains one.
+    fn as_leaf(&self) -> Option<&T>;
+    /// Returns a mutable reference to the nested `T`, if `self` contains one.
+    fn as_leaf_mut(&mut self) -> Option<&mut T>;
+}
+
+/// Implemented for each type `Self` which can be converted into the amassed enum `Root`,
+/// when `Leaf` is included in the `impls` option of [amass].  
+/// Unlike `Into<Root>`, this cannot be implemented manually, so it only accepts the types found by amass.
+/// ```rust
+/// # use amass::{Leaf, amass_telety};
+/// pub struct Stout;
+///
+/// #[amass_telety(crate)]
+/// pub enum Ale {
+///     Stout(Stout),
+/// }
+///
+/// #[amass_telety(crate, impls(From, Leaf))]
+/// pub enum Beer {
+///     Ale(Ale),
+/// }
+///
+/// fn route<T: Leaf<Beer>>(_: &T) -> &'static [&'static str] {
+///     T::PATH
+/// }
+///
+/// fn main() {
+///     assert_eq!(route(&Stout), ["Beer::Ale", "Ale::Stout"]);
+///     assert_eq!(<Ale as Leaf<Beer>>::DEPTH, 1);
+/// }
+/// ```
+pub trait Leaf<Root>: __private::Sealed<Root> {
+    /// The variants through which `Self` is nested, outermost first, e.g. `["Beer::Ale", "Ale::Stout"]`.
+    const PATH: &'static [&'static str];
+    /// The number of variants through which `Self` is nested.
+    const DEPTH: usize;
+}
+
+/// A conversion into an amassed type, as listed by the const generated with `conversions = NAME`.
+/// ```rust
+/// # use amass::amass_telety;
+/// pub struct Stout;
+///
+/// #[amass_telety(crate)]
+/// pub enum Ale {
+///     Stout(Stout),
+/// }
+///
+/// #[amass_telety(crate, conversions = CONVERSIONS)]
+/// pub enum Beer {
+///     Ale(Ale),
+/// }
+///
+/// fn main() {
+///     let stout = &Beer::CONVERSIONS[1];
+///     assert_eq!(stout.leaf(), "Stout");
+///     assert_eq!(stout.variants(), ["Beer::Ale", "Ale::Stout"]);
+///     assert_eq!(stout.depth(), 2);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Conversion {
+    leaf: &'static str,
+    variants: &'static [&'static str],
+}
+
+impl Conversion {
+    #[doc(hidden)]
+    pub const fn new(leaf: &'static str, variants: &'static [&'static str]) -> Self {
+        Self { leaf, variants }
+    }
+
+    /// The type converted from, as written without its path.
+    pub fn leaf(&self) -> &'static str {
+        self.leaf
+    }
+
+    /// The variants the leaf is converted through, outermost first, as `Type::Variant`.
+    pub fn variants(&self) -> &'static [&'static str] {
+        self.variants
+    }
+
+    /// The number of variants in the conversion.
+    pub fn depth(&self) -> usize {
+        self.variants.len()
+    }
+}
+
+/// The variants of a value of an amassed type, as returned by the `amass_path` method generated by `path`.  
+/// Fields of types which also use `path` are followed, so the path ends at the innermost such value.
+/// Fields whose type is a generic parameter of the enum are not followed.
+/// Displayed as `Root::Variant::NestedVariant`.
+/// ```rust
+/// # use amass::amass_telety;
+/// #[amass_telety(crate, path)]
+/// pub enum IPA {
+///     WestCoast,
+///     NewEngland,
+/// }
+///
+/// #[amass_telety(crate, path)]
+/// pub enum Ale {
+///     IPA(IPA),
+/// }
+///
+/// #[amass_telety(crate, path)]
+/// pub enum Beer {
+///     Ale(Ale),
+/// }
+///
+/// fn main() {
+///     let beer: Beer = IPA::WestCoast.into();
+///     let path = beer.amass_path();
+///     assert_eq!(path.variants(), ["Ale", "IPA", "WestCoast"]);
+///     assert_eq!(path.depth(), 3);
+///     assert_eq!(path.to_string(), "Beer::Ale::IPA::WestCoast");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AmassPath {
+    root: &'static str,
+    variants: Vec<&'static str>,
+}
+
+impl AmassPath {
+    #[doc(hidden)]
+    pub fn new(root: &'static str) -> Self {
+        Self {
+            root,
+            variants: Vec::new(),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn push(&mut self, variant: &'static str) {
+        self.variants.push(variant);
+    }
+
+    /// The name of the type `amass_path` was called on.
+    pub fn root(&self) -> &'static str {
+        self.root
+    }
+
+    /// The names of the variants, outermost first.
+    pub fn variants(&self) -> &[&'static str] {
+        &self.variants
+    }
+
+    /// The number of variants in the path.
+    pub fn depth(&self) -> usize {
+        self.variants.len()
+    }
+}
+
+impl std::fmt::Display for AmassPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.root)?;
+        for variant in &self.variants {
+            write!(f, "::{variant}")?;
+        }
+        Ok(())
+    }
+}
+
 #[doc(hidden)]
 pub mod __private {
+    use std::{rc::Rc, sync::Arc};
+
+    use crate::AmassPath;
+
+    /// Prevents [Leaf](crate::Leaf) from being implemented outside of generated impls.
+    pub trait Sealed<Root> {}
+
+    /// Implemented for amassed types which use `path`, to follow their variants in [AmassPath]
+    pub trait AmassPathed {
+        fn push_path(&self, path: &mut AmassPath);
+    }
+
+    impl<T: AmassPathed + ?Sized> AmassPathed for Box<T> {
+        fn push_path(&self, path: &mut AmassPath) {
+            (**self).push_path(path);
+        }
+    }
+
+    impl<T: AmassPathed + ?Sized> AmassPathed for Rc<T> {
+        fn push_path(&self, path: &mut AmassPath) {
+            (**self).push_path(path);
+        }
+    }
+
+    impl<T: AmassPathed + ?Sized> AmassPathed for Arc<T> {
+        fn push_path(&self, path: &mut AmassPath) {
+            (**self).push_path(path);
+        }
+    }
+
+    /// Follows a field if its type is amassed, otherwise does nothing.
+    /// `(&PathProbe(field)).push_field_path(path)` prefers [PathProbeNested] if it applies.
+    pub struct PathProbe<'a, T: ?Sized>(pub &'a T);
+
+    pub trait PathProbeNested {
+        fn push_field_path(&self, path: &mut AmassPath);
+    }
+
+    impl<T: AmassPathed + ?Sized> PathProbeNested for PathProbe<'_, T> {
+        fn push_field_path(&self, path: &mut AmassPath) {
+            self.0.push_path(path);
+        }
+    }
+
+    pub trait PathProbeLeaf {
+        fn push_field_path(&self, path: &mut AmassPath);
+    }
+
+    impl<T: ?Sized> PathProbeLeaf for &PathProbe<'_, T> {
+        fn push_field_path(&self, _path: &mut AmassPath) {}
+    }
+
     pub use amass_macro::amass_apply;
+    pub use amass_macro::amass_delegate;
     pub use amass_macro::amass_from;
+    pub use amass_macro::amass_generated;
 
     pub use telety;
 
@@ -224,4 +1019,14 @@ pub mod __private {
     }
 
     pub use _require_telety_error as require_telety_error;
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! _require_telety_trait_error {
+        ($($tokens:tt)*) => {
+            compile_error!("`delegate` requires '#[telety(...)]' on the trait");
+        };
+    }
+
+    pub use _require_telety_trait_error as require_telety_trait_error;
 }
@@ -1,5 +1,7 @@
 mod util;
 
+use amass::{AsLeaf, Leaf};
+
 use util::types::*;
 
 #[test]
@@ -18,3 +20,576 @@ fn test() {
         println!("{beer:?}");
     }
 }
+
+#[test]
+fn try_from() {
+    assert!(matches!(
+        Pilsner::try_from(Brew::from(Pilsner)),
+        Ok(Pilsner)
+    ));
+    assert!(matches!(
+        IPA::try_from(Brew::from(IPA::NewEngland)),
+        Ok(IPA::NewEngland)
+    ));
+    assert!(matches!(
+        Ale::try_from(Brew::from(Stout::Irish)),
+        Ok(Ale::Stout(Stout::Irish))
+    ));
+    assert!(matches!(
+        Lager::try_from(Brew::from(Wheat::Hefeweizen)),
+        Err(Brew::Ale(Ale::Wheat(Wheat::Hefeweizen)))
+    ));
+}
+
+#[test]
+fn as_leaf() {
+    let mut brew = Brew::from(Stout::Irish);
+    assert!(matches!(
+        AsLeaf::<Stout>::as_leaf(&brew),
+        Some(Stout::Irish)
+    ));
+    assert!(matches!(
+        AsLeaf::<Ale>::as_leaf(&brew),
+        Some(Ale::Stout(Stout::Irish))
+    ));
+    assert!(AsLeaf::<Lager>::as_leaf(&brew).is_none());
+    assert!(AsLeaf::<Wheat>::as_leaf_mut(&mut brew).is_none());
+
+    if let Some(stout) = AsLeaf::<Stout>::as_leaf_mut(&mut brew) {
+        *stout = Stout::Oatmeal;
+    }
+    assert!(matches!(brew, Brew::Ale(Ale::Stout(Stout::Oatmeal))));
+}
+
+#[test]
+fn action_override() {
+    assert!(matches!(
+        DiamondBottom::from(DiamondTop),
+        DiamondBottom::Right(DiamondRight::Top(DiamondTop))
+    ));
+    assert!(matches!(
+        DiamondSides::from(DiamondLeft::Top(DiamondTop)),
+        DiamondSides::Left(DiamondLeft::Top(DiamondTop))
+    ));
+}
+
+#[test]
+fn on_conflict() {
+    assert!(matches!(
+        DiamondPrefer::from(DiamondTop),
+        DiamondPrefer::Right(DiamondRight::Top(DiamondTop))
+    ));
+    assert!(matches!(
+        DiamondFirst::from(DiamondTop),
+        DiamondFirst::Left(DiamondLeft::Top(DiamondTop))
+    ));
+    assert!(matches!(
+        Drink::from(Stout::Irish),
+        Drink::Beer(Beer::Ale(Ale::Stout(Stout::Irish)))
+    ));
+    assert!(matches!(
+        Drink::from(Generic2::<u8, u8>::T((1,))),
+        Drink::Generic(Generic1::G2(Generic2::T((1,))))
+    ));
+}
+
+#[test]
+fn newtype() {
+    assert!(matches!(
+        Pint::from(Bock),
+        Pint(Beer::Lager(Lager::Bock(Bock)))
+    ));
+    assert!(matches!(
+        Order::from(Stout::Irish),
+        Order {
+            pint: Pint(Beer::Ale(Ale::Stout(Stout::Irish)))
+        }
+    ));
+    assert!(matches!(
+        Stout::try_from(Order::from(Stout::Oatmeal)),
+        Ok(Stout::Oatmeal)
+    ));
+    assert!(matches!(
+        Stout::try_from(Order::from(Pilsner)),
+        Err(Order { .. })
+    ));
+    assert!(AsLeaf::<Beer>::as_leaf(&Order::from(Pilsner)).is_some());
+}
+
+#[test]
+fn wrapper() {
+    assert!(
+        matches!(Cellar::from(Bock), Cellar::Boxed(lager) if matches!(*lager, Lager::Bock(Bock)))
+    );
+    assert!(matches!(
+        Cellar::from(Box::new(Lager::Bock(Bock))),
+        Cellar::Boxed(_)
+    ));
+    assert!(matches!(Cellar::from(DiamondTop), Cellar::Atomic { .. }));
+
+    assert!(matches!(Bock::try_from(Cellar::from(Bock)), Ok(Bock)));
+    assert!(matches!(
+        Pilsner::try_from(Cellar::from(Bock)),
+        Err(Cellar::Boxed(_))
+    ));
+    assert!(matches!(
+        Stout::try_from(Cellar::from(Stout::Irish)),
+        Ok(Stout::Irish)
+    ));
+
+    let mut cellar = Cellar::from(Stout::Irish);
+    assert!(matches!(
+        AsLeaf::<Stout>::as_leaf(&cellar),
+        Some(Stout::Irish)
+    ));
+    if let Some(stout) = AsLeaf::<Stout>::as_leaf_mut(&mut cellar) {
+        *stout = Stout::Oatmeal;
+    }
+    assert!(matches!(
+        AsLeaf::<Stout>::as_leaf(&cellar),
+        Some(Stout::Oatmeal)
+    ));
+
+    // Shared values cannot be mutably borrowed or moved out
+    let Cellar::Shared(ale) = &cellar else {
+        unreachable!()
+    };
+    let _shared = ale.clone();
+    assert!(AsLeaf::<Stout>::as_leaf_mut(&mut cellar).is_none());
+    assert!(matches!(Stout::try_from(cellar), Err(Cellar::Shared(_))));
+}
+
+#[test]
+fn cycle() {
+    assert!(matches!(Expr::from(Literal), Expr::Literal(Literal)));
+    assert!(matches!(
+        Block::from(Literal),
+        Block::Expr(Expr::Literal(Literal))
+    ));
+    assert!(matches!(
+        Expr::from(Block::Expr(Expr::Literal(Literal))),
+        Expr::Block(_)
+    ));
+    assert!(matches!(
+        Recursive::from(Box::new(Recursive::Generic(Generic2::T((1,))))),
+        Recursive::Boxed(_)
+    ));
+    assert!(matches!(Unary::from(Literal), Unary::Literal(Literal)));
+    assert!(matches!(
+        Unary::from(Box::new(Unary::Literal(Literal))),
+        Unary::Neg(_)
+    ));
+    let negated = Negated::from(Literal);
+    assert!(matches!(negated, Negated::Unary(Unary::Literal(Literal))));
+    assert!(matches!(Negated::from(Box::new(negated)), Negated::Neg(_)));
+    assert!(matches!(
+        Negated::from(Box::new(Unary::Literal(Literal))),
+        Negated::Unary(Unary::Neg(_))
+    ));
+}
+
+#[test]
+fn type_parameter() {
+    assert!(matches!(Wrap::from(1u8), Wrap::Inner(1)));
+    assert!(matches!(AsLeaf::<u8>::as_leaf(&Wrap::from(1u8)), Some(1)));
+    assert!(matches!(
+        Either::<u8>::from(Wheat::Hefeweizen),
+        Either::Right(Wheat::Hefeweizen)
+    ));
+    assert!(matches!(
+        Wrapped::from(Stout::Irish),
+        Wrapped::Wrap(Wrap::Inner(Stout::Irish))
+    ));
+    assert!(matches!(
+        Wrapped::from(IPA::Imperial),
+        Wrapped::Either(Either::Left(IPA::Imperial))
+    ));
+
+    assert!(matches!(
+        Generic2::<u8, u16>::from((1u8, 2u16)),
+        Generic2::TU((1, 2))
+    ));
+    assert!(matches!(
+        Generic1::<u8>::from((1u8,)),
+        Generic1::G2(Generic2::T((1,)))
+    ));
+    assert!(matches!(
+        Generic1::<u8>::from((1u8, 2u8)),
+        Generic1::G2(Generic2::TU((1, 2)))
+    ));
+    assert!(matches!(
+        Sourced::<Stout>::from(Literal),
+        Sourced::Literal(Literal)
+    ));
+}
+
+#[test]
+fn non_path() {
+    assert!(matches!(Raw::from((1, 2)), Raw::Pair((1, 2))));
+    assert!(matches!(Raw::from([1, 2, 3, 4]), Raw::Buf([1, 2, 3, 4])));
+    assert!(matches!(Raw::from("name"), Raw::Name("name")));
+    assert!(matches!(Raw::from(&[1u8][..]), Raw::Bytes([1])));
+    assert!(matches!(<&str>::try_from(Raw::from("name")), Ok("name")));
+    assert!(AsLeaf::<[u8; 4]>::as_leaf(&Raw::from((1, 2))).is_none());
+
+    assert!(matches!(
+        Message::from("name"),
+        Message::Raw(Raw::Name("name"))
+    ));
+    assert!(matches!(
+        Message::from((Stout::Irish, Stout::Oatmeal)),
+        Message::Stout((Stout::Irish, Stout::Oatmeal))
+    ));
+    assert!(matches!(
+        Generic2::<u8, u8>::from((1, 2)),
+        Generic2::TU((1, 2))
+    ));
+    assert!(matches!(Drink::from(()), Drink::Water(())));
+}
+
+#[test]
+fn tuple() {
+    assert!(matches!(Shape::from((1, 2)), Shape::Point(1, 2)));
+    assert!(matches!(
+        Shape::from((1usize, 2usize)),
+        Shape::Span { start: 1, end: 2 }
+    ));
+    assert!(matches!(
+        <(usize, usize)>::try_from(Shape::from((1usize, 2usize))),
+        Ok((1, 2))
+    ));
+    assert!(matches!(
+        <(i32, i32)>::try_from(Shape::from(())),
+        Err(Shape::Unit(()))
+    ));
+    assert!(matches!(
+        Canvas::from((3, 4)),
+        Canvas::Shape(Shape::Point(3, 4))
+    ));
+}
+
+#[test]
+fn fill() {
+    assert!(matches!(
+        Tab::from(Stout::Irish),
+        Tab::Stout {
+            stout: Stout::Irish,
+            count: 0
+        }
+    ));
+    assert!(matches!(Tab::from(Bock), Tab::Lager(Lager::Bock(Bock), 2)));
+    assert!(matches!(
+        Bar::from(Pilsner),
+        Bar::Tab(Tab::Lager(Lager::Pilsner(Pilsner), 2))
+    ));
+    assert!(matches!(
+        Stout::try_from(Tab::from(Stout::Oatmeal)),
+        Ok(Stout::Oatmeal)
+    ));
+    assert!(matches!(
+        AsLeaf::<Bock>::as_leaf(&Tab::from(Bock)),
+        Some(Bock)
+    ));
+}
+
+#[test]
+fn capture() {
+    fn fail() -> Result<(), LocatedOuter> {
+        Err(Wheat::Hefeweizen)?
+    }
+    let line = line!() - 2;
+
+    let Err(LocatedOuter::Located(Located::Wheat { wheat, location }, outer_location)) = fail()
+    else {
+        unreachable!()
+    };
+    assert!(matches!(wheat, Wheat::Hefeweizen));
+    assert_eq!(location.file(), file!());
+    assert_eq!(location.line(), line);
+    assert_eq!(outer_location.line(), line);
+
+    // Nested types fill their fields even if the root does not capture
+    let Uncaptured::Located(Located::Wheat { location, .. }) = Wheat::Hefeweizen.into();
+    assert_eq!(location.line(), line!() - 1);
+
+    assert!(matches!(
+        Traced::from(Wheat::Hefeweizen),
+        Traced::Wheat(Wheat::Hefeweizen, _)
+    ));
+}
+
+#[test]
+fn max_depth() {
+    assert!(matches!(
+        Round::from(Beer::from(Bock)),
+        Round::Pint(Pint(Beer::Lager(Lager::Bock(Bock))))
+    ));
+    assert!(matches!(
+        Round::from(Stout::Irish),
+        Round::Ale(Ale::Stout(Stout::Irish))
+    ));
+}
+
+#[test]
+fn leaf() {
+    fn path<T: amass::Leaf<Brew>>(_: T) -> &'static [&'static str] {
+        T::PATH
+    }
+
+    assert_eq!(path(Stout::Irish), ["Brew::Ale", "Ale::Stout"]);
+    assert_eq!(<Ale as amass::Leaf<Brew>>::PATH, ["Brew::Ale"]);
+    assert_eq!(<Bock as amass::Leaf<Brew>>::DEPTH, 2);
+    assert_eq!(
+        <Bock as amass::Leaf<Cellar>>::PATH,
+        ["Cellar::Boxed", "Lager::Bock"]
+    );
+}
+
+#[test]
+fn amass_path() {
+    let path = Tap::<()>::from(Cask::Full).amass_path();
+    assert_eq!(path.root(), "Tap");
+    assert_eq!(path.variants(), ["Keg", "Full"]);
+    assert_eq!(path.depth(), 2);
+    assert_eq!(path.to_string(), "Tap::Keg::Full");
+
+    // `Beer` does not use `path`, so the path ends at `Tap::Beer`
+    assert_eq!(
+        Tap::<()>::from(IPA::WestCoast).amass_path().to_string(),
+        "Tap::Beer"
+    );
+    // Fields of generic parameter types are not followed
+    assert_eq!(Tap::Inner(Cask::Empty).amass_path().variants(), ["Inner"]);
+}
+
+#[test]
+fn kind() {
+    assert_eq!(Style::from(Pilsner).kind(), BeerKind::Pilsner);
+    assert_eq!(Style::from(IPA::WestCoast).kind(), BeerKind::IpaWestCoast);
+    // `Stout` is not amassed, so its variants share a kind
+    assert_eq!(Style::from(Stout::Oatmeal).kind(), BeerKind::Stout);
+    assert_eq!(
+        BeerKind::ALL,
+        [
+            BeerKind::Pilsner,
+            BeerKind::Bock,
+            BeerKind::IpaWestCoast,
+            BeerKind::IpaNewEngland,
+            BeerKind::IpaImperial,
+            BeerKind::Stout,
+            BeerKind::Wheat,
+        ]
+    );
+    for (index, kind) in BeerKind::ALL.iter().enumerate() {
+        assert_eq!(kind.index(), index);
+    }
+    // The kind enum is a real item beside the root, so its variants can be imported
+    {
+        use util::types::BeerKind::*;
+        assert_eq!(Style::from(util::types::Bock).kind(), Bock);
+    }
+
+    // Wrappers and structs are matched through
+    assert_eq!(Cellar::from(Bock).kind(), CellarKind::Bock);
+    assert_eq!(Cellar::from(IPA::Imperial).kind(), CellarKind::IpaImperial);
+    assert_eq!(
+        Order::from(IPA::NewEngland).kind(),
+        OrderKind::IpaNewEngland
+    );
+}
+
+#[test]
+fn flat() {
+    let flat = FlatBeer::from(Menu::from(IPA::Imperial));
+    assert!(matches!(flat, FlatBeer::IPA(IPA::Imperial)));
+    let menu = Menu::from(flat);
+    assert!(matches!(menu, Menu::Ale(Ale::IPA(IPA::Imperial))));
+
+    let menu = Menu::from(FlatBeer::Bock(Bock));
+    assert!(matches!(menu, Menu::Lager(Lager::Bock(Bock))));
+    assert!(matches!(FlatBeer::from(menu), FlatBeer::Bock(Bock)));
+    // The flat enum is a real item beside the root, so its variants can be imported
+    {
+        use util::types::FlatBeer::*;
+        let flat = FlatBeer::from(Menu::from(util::types::Pilsner));
+        assert!(matches!(flat, Pilsner(_)));
+    }
+
+    // Boxed values are flattened, shared values stay whole
+    let flat = FlatCellar::from(Cellar::from(Pilsner));
+    assert!(matches!(flat, FlatCellar::Pilsner(Pilsner)));
+    assert!(
+        matches!(Cellar::from(flat), Cellar::Boxed(lager) if matches!(*lager, Lager::Pilsner(_)))
+    );
+    let flat = FlatCellar::from(Cellar::Shared(std::rc::Rc::new(Ale::Stout(Stout::Irish))));
+    assert!(matches!(flat, FlatCellar::Shared(ale) if matches!(*ale, Ale::Stout(Stout::Irish))));
+
+    // Tuple variants hold their fields as a tuple
+    let flat = FlatCanvas::from(Canvas::from((1, 2)));
+    assert!(matches!(flat, FlatCanvas::Point((1, 2))));
+    assert!(matches!(
+        Canvas::from(FlatCanvas::Span((3, 4))),
+        Canvas::Shape(Shape::Span { start: 3, end: 4 })
+    ));
+
+    // A boxed leaf is moved out of its `Box`
+    let flat = FlatCanvas::from(Canvas::from(Bock));
+    assert!(matches!(flat, FlatCanvas::Frame(Bock)));
+}
+
+#[test]
+fn delegate() {
+    let mut error = AppError::from(ParseError { line: 7 });
+    assert_eq!(error.code(), 107);
+    // Overridden default methods are delegated too
+    assert_eq!(error.message("error"), "error: line 7");
+    error.reset();
+    assert_eq!(error.code(), 100);
+
+    let error = AppError::Context {
+        io: IoError,
+        context: "reading",
+    };
+    assert_eq!(error.message("error"), "error: 1");
+
+    // Delegated through recursive variants
+    let error = ChainError::Chain(Box::new(ChainError::Io(IoError)));
+    assert_eq!(error.code(), 1);
+    let error = InnerError::Outer(Box::new(OuterError::Parse(ParseError { line: 3 })));
+    assert_eq!(error.code(), 103);
+    let error = OuterError::Inner(Box::new(InnerError::Io(IoError)));
+    assert_eq!(error.message("error"), "error: 1");
+}
+
+#[test]
+fn error() {
+    use std::error::Error as _;
+
+    let error = StartupError::from(IoError);
+    assert_eq!(error.to_string(), "reading config: io error");
+    let source = error.source().expect("config error is the source");
+    assert_eq!(source.to_string(), "reading config: io error");
+    let source = source.source().expect("io error is the source");
+    assert_eq!(source.to_string(), "io error");
+    assert!(source.source().is_none());
+
+    let error = StartupError::from(ParseError { line: 3 });
+    assert_eq!(
+        error.to_string(),
+        "parsing arguments: parse error on line 3"
+    );
+
+    // Variants without a single field have no source
+    let error = StartupError::from(ConfigError::Missing);
+    assert_eq!(error.to_string(), "Missing");
+    assert!(error.source().unwrap().source().is_none());
+    assert_eq!(ConfigError::Range(2, 1).to_string(), "invalid range");
+
+    let error = RetryError::Retry(Box::new(RetryError::from(IoError)));
+    assert_eq!(error.to_string(), "retrying: io error");
+    let source = error.source().expect("retried error is the source");
+    assert_eq!(source.source().unwrap().to_string(), "io error");
+}
+
+#[test]
+fn with() {
+    let error = std::io::Error::other("disk full");
+    let task = Task::from(error);
+    assert!(matches!(
+        task,
+        Task::Fetch(Fetch::Io(IoContext { retries: 3, ref error })) if error.to_string() == "disk full"
+    ));
+
+    let fetch = Fetch::from(b"name".to_vec());
+    assert!(matches!(fetch, Fetch::Label(Label(ref label)) if label == "name"));
+    assert_eq!(
+        <std::io::Error as Leaf<Fetch>>::PATH,
+        &["Fetch::Io"] as &[&str]
+    );
+
+    // Other variants are unaffected
+    assert!(matches!(u32::try_from(Fetch::from(2u32)), Ok(2)));
+}
+
+#[test]
+fn also_from() {
+    fn parse(s: &str) -> Result<u32, AppError> {
+        Ok(s.parse::<u32>()?)
+    }
+
+    // Converted through `ParseError`'s own `From` impl, then boxed
+    assert!(matches!(
+        parse("x"),
+        Err(AppError::Input(InputError::Parse(ref error))) if error.line == 1
+    ));
+    let error = InputError::from("x".parse::<f32>().unwrap_err());
+    assert!(matches!(error, InputError::Parse(ref error) if error.line == 2));
+}
+
+#[test]
+#[allow(deprecated)]
+fn cfg() {
+    assert_eq!(GatedKind::ALL, &[GatedKind::Io, GatedKind::Context]);
+    assert_eq!(Gated::from(IoError).to_string(), "io error");
+    assert_eq!(Gated::from(IoError).code(), 1);
+
+    // The root's own deprecated variants are converted without a warning
+    let gated = Gated::from(ParseError { line: 1 });
+    assert_eq!(gated.kind(), GatedKind::Context);
+}
+
+#[test]
+fn conversions() {
+    let leaves: Vec<_> = Cellar::CONVERSIONS.iter().map(|c| c.leaf()).collect();
+    assert_eq!(
+        leaves,
+        [
+            "Lager",
+            "Pilsner",
+            "Bock",
+            "Box<Lager>",
+            "Ale",
+            "IPA",
+            "Stout",
+            "Wheat",
+            "Rc<Ale>",
+            "DiamondTop",
+            "Arc<DiamondTop>",
+        ]
+    );
+    let bock = &Cellar::CONVERSIONS[2];
+    assert_eq!(bock.variants(), ["Cellar::Boxed", "Lager::Bock"]);
+    assert_eq!(bock.depth(), 2);
+
+    // Conversions through compiled out variants are not listed
+    let leaves: Vec<_> = Gated::CONVERSIONS.iter().map(|c| c.leaf()).collect();
+    assert_eq!(leaves, ["IoError", "ParseError"]);
+}
+
+#[test]
+fn explain() {
+    assert_eq!(
+        Flight::EXPLAIN,
+        [
+            "`From<Lager> for Flight`, `TryFrom<Flight> for Lager`, `AsLeaf<Lager> for Flight` via `Flight::Lager`",
+            "`From<Pilsner> for Flight`, `TryFrom<Flight> for Pilsner`, `AsLeaf<Pilsner> for Flight` via `Flight::Lager -> Lager::Pilsner`",
+            "`From<Bock> for Flight`, `TryFrom<Flight> for Bock`, `AsLeaf<Bock> for Flight` via `Flight::Lager -> Lager::Bock`",
+            // Tuples cannot be borrowed as a tuple
+            "`From<(Pilsner, Bock)> for Flight`, `TryFrom<Flight> for (Pilsner, Bock)` via `Flight::Pair`",
+        ]
+    );
+
+    // Conversions through compiled out variants are not explained
+    assert_eq!(
+        Gated::EXPLAIN,
+        [
+            "`From<IoError> for Gated` via `Gated::Io`",
+            "`From<ParseError> for Gated` via `Gated::Context`",
+        ]
+    );
+}
+
+#[test]
+fn ui() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,12 @@
+use amass::amass_telety;
+
+pub trait Source {
+    type Item;
+}
+
+#[amass_telety(crate)]
+pub enum Wrap<T: Source> {
+    Inner(T::Item),
+}
+
+fn main() {}
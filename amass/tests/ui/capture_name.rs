@@ -0,0 +1,15 @@
+use amass::amass_telety;
+
+pub struct Leaf;
+
+// Not `core::panic::Location`, so the variant is not converted
+pub struct Location<'a>(&'a str);
+
+#[amass_telety(crate, capture = location)]
+pub enum Error {
+    Leaf(Leaf, &'static Location<'static>),
+}
+
+fn main() {
+    let _ = Error::from(Leaf);
+}
@@ -0,0 +1,18 @@
+#![deny(deprecated)]
+
+use amass::amass_telety;
+
+pub struct OldError;
+
+#[amass_telety(crate)]
+pub enum NetError {
+    #[deprecated(note = "no longer returned")]
+    Old(OldError),
+}
+
+#[amass_telety(crate)]
+pub enum Error {
+    Net(NetError),
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use amass::amass_telety;
+
+#[amass_telety(crate)]
+pub enum Celsius {
+    Reading(i32),
+}
+
+#[amass_telety(crate)]
+pub enum Fahrenheit {
+    Reading(i32),
+}
+
+// The whole tree is traversed at once for `kind`, so duplicates across subtrees are found
+#[amass_telety(crate, kind = TemperatureKind)]
+pub enum Temperature {
+    Celsius(Celsius),
+    Fahrenheit(Fahrenheit),
+}
+
+fn main() {}
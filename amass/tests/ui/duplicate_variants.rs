@@ -0,0 +1,9 @@
+use amass::amass_telety;
+
+#[amass_telety(crate)]
+pub enum Number {
+    Small(i32),
+    Large(i32),
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use amass::amass_telety;
+
+pub struct Top;
+
+#[amass_telety(crate, on_conflict = first)]
+pub enum Sides {
+    Left(Top),
+    #[amass_action(prefer)]
+    Right(Top),
+}
+
+#[amass_telety(crate)]
+pub enum Bottom {
+    Sides(Sides),
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use amass::amass_telety;
+
+pub struct DiamondTop;
+
+#[amass_telety(crate)]
+pub enum DiamondLeft {
+    Top(DiamondTop),
+}
+
+#[amass_telety(crate)]
+pub enum DiamondRight {
+    Top(DiamondTop),
+}
+
+#[amass_telety(crate, on_conflict = error)]
+pub enum DiamondBottom {
+    Left(DiamondLeft),
+    Right(DiamondRight),
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use amass::amass_telety;
+
+pub struct Leaf;
+
+#[amass_telety(crate, on_cycle = error)]
+pub enum A {
+    Leaf(Leaf),
+    B(Box<B>),
+}
+
+#[amass_telety(crate)]
+pub enum B {
+    A(A),
+}
+
+fn main() {}
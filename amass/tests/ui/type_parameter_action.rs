@@ -0,0 +1,12 @@
+use amass::amass_telety;
+
+pub struct Wheat;
+
+#[amass_telety(crate)]
+pub enum Either<T> {
+    #[amass_action(shallow)]
+    Left(T),
+    Right(Wheat),
+}
+
+fn main() {}
@@ -1,4 +1,4 @@
-#![allow(dead_code)]
+#![allow(dead_code, clippy::upper_case_acronyms)]
 
 use amass::amass_telety;
 use telety::telety;
@@ -11,6 +11,27 @@ pub(crate) enum Beer {
     Ale(Ale),
 }
 
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf, Leaf))]
+#[derive(Debug)]
+pub(crate) enum Brew {
+    Lager(Lager),
+    Ale(Ale),
+}
+
+#[amass_telety(crate::util::types, kind = BeerKind)]
+#[derive(Debug)]
+pub(crate) enum Style {
+    Lager(Lager),
+    Ale(Ale),
+}
+
+#[amass_telety(crate::util::types, flat = FlatBeer)]
+#[derive(Debug)]
+pub(crate) enum Menu {
+    Lager(Lager),
+    Ale(Ale),
+}
+
 #[amass_telety(crate::util::types)]
 #[derive(Debug)]
 #[non_exhaustive]
@@ -82,3 +103,470 @@ pub(crate) enum Generic2<T, U> {
 enum Private {
     A(i32),
 }
+
+#[telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) struct DiamondTop;
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum DiamondLeft {
+    Top(DiamondTop),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum DiamondRight {
+    Top(DiamondTop),
+}
+
+#[amass_telety(crate::util::types, override(DiamondLeft::Top = ignore))]
+#[derive(Debug)]
+pub(crate) enum DiamondBottom {
+    Left(DiamondLeft),
+    Right(DiamondRight),
+}
+
+#[amass_telety(crate::util::types, override(DiamondTop = ignore))]
+#[derive(Debug)]
+pub(crate) enum DiamondSides {
+    Left(DiamondLeft),
+    Right(DiamondRight),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum DiamondPrefer {
+    Left(DiamondLeft),
+    #[amass_action(prefer)]
+    Right(DiamondRight),
+}
+
+#[amass_telety(crate::util::types, on_conflict = first)]
+#[derive(Debug)]
+pub(crate) enum DiamondFirst {
+    Left(DiamondLeft),
+    Right(DiamondRight),
+}
+
+#[amass_telety(crate::util::types, on_conflict = error)]
+#[derive(Debug)]
+pub(crate) enum Drink {
+    Beer(Beer),
+    Generic(Generic1<u8>),
+    Water(()),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) struct Pint(pub(crate) Beer);
+
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf), kind = OrderKind)]
+#[derive(Debug)]
+pub(crate) struct Order {
+    pub(crate) pint: Pint,
+}
+
+#[amass_telety(
+    crate::util::types,
+    impls(From, TryFrom, AsLeaf, Leaf),
+    kind = CellarKind,
+    flat = FlatCellar,
+    conversions = CONVERSIONS
+)]
+#[derive(Debug)]
+pub(crate) enum Cellar {
+    Boxed(Box<Lager>),
+    Shared(std::rc::Rc<Ale>),
+    Atomic { top: std::sync::Arc<DiamondTop> },
+}
+
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf), explain)]
+#[derive(Debug)]
+pub(crate) enum Flight {
+    Lager(Lager),
+    #[amass_action(tuple)]
+    Pair(Pilsner, Bock),
+}
+
+#[telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) struct Literal;
+
+#[amass_telety(crate::util::types, on_cycle = ignore)]
+#[derive(Debug)]
+pub(crate) enum Expr {
+    Literal(Literal),
+    Block(Box<Block>),
+}
+
+#[amass_telety(crate::util::types, on_cycle = ignore)]
+#[derive(Debug)]
+pub(crate) enum Block {
+    Expr(Expr),
+}
+
+// Recursive types compile without any options
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Unary {
+    Literal(Literal),
+    Neg(Box<Unary>),
+}
+
+// A wrapped enclosing type is not reflected, so it is not a cycle
+#[amass_telety(crate::util::types, on_cycle = error)]
+#[derive(Debug)]
+pub(crate) enum Negated {
+    Neg(Box<Self>),
+    Unary(Unary),
+}
+
+#[amass_telety(crate::util::types, on_cycle = ignore)]
+#[derive(Debug)]
+pub(crate) enum Recursive<T> {
+    Boxed(Box<Self>),
+    Generic(Generic2<T, T>),
+}
+
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf, Leaf))]
+#[derive(Debug)]
+pub(crate) enum Wrap<T> {
+    Inner(T),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Either<T> {
+    Left(T),
+    Right(Wheat),
+}
+
+pub(crate) trait Source {
+    type Item;
+}
+
+impl Source for Stout {
+    type Item = Pilsner;
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Sourced<T: Source> {
+    // Associated types of parameters cannot be converted
+    #[amass_action(ignore)]
+    Item(T::Item),
+    Literal(Literal),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Wrapped {
+    Wrap(Wrap<Stout>),
+    Either(Either<IPA>),
+}
+
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf))]
+#[derive(Debug)]
+pub(crate) enum Raw<'a> {
+    Pair((u8, u8)),
+    Buf([u8; 4]),
+    Name(&'a str),
+    Bytes(&'a [u8]),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Message {
+    Raw(Raw<'static>),
+    Stout((Stout, Stout)),
+}
+
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf))]
+#[derive(Debug)]
+pub(crate) enum Shape {
+    #[amass_action(tuple)]
+    Point(i32, i32),
+    #[amass_action(tuple)]
+    Span {
+        start: usize,
+        end: usize,
+    },
+    Unit(()),
+}
+
+#[amass_telety(crate::util::types, flat = FlatCanvas)]
+#[derive(Debug)]
+pub(crate) enum Canvas {
+    Shape(Shape),
+    Frame(Box<Bock>),
+}
+
+// `Beer` does not use `path`, so paths through `Tap::Beer` end there
+#[amass_telety(crate::util::types, path)]
+#[derive(Debug)]
+pub(crate) enum Tap<T> {
+    Keg(Box<Keg>),
+    Beer(Beer),
+    Inner(T),
+}
+
+#[amass_telety(crate::util::types, path)]
+#[derive(Debug)]
+pub(crate) struct Keg {
+    pub(crate) cask: Cask,
+}
+
+#[amass_telety(crate::util::types, path)]
+#[derive(Debug)]
+pub(crate) enum Cask {
+    Full,
+    Empty,
+}
+
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf))]
+#[derive(Debug)]
+pub(crate) enum Tab {
+    Stout {
+        #[amass_field]
+        stout: Stout,
+        count: u32,
+    },
+    Lager(#[amass_field] Lager, #[amass_fill(2)] u8),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Bar {
+    Tab(Tab),
+}
+
+#[amass_telety(crate::util::types, capture = location)]
+#[derive(Debug)]
+pub(crate) enum Located {
+    Wheat {
+        wheat: Wheat,
+        location: &'static std::panic::Location<'static>,
+    },
+}
+
+#[amass_telety(crate::util::types, capture = location)]
+#[derive(Debug)]
+pub(crate) enum LocatedOuter {
+    Located(Located, &'static core::panic::Location<'static>),
+}
+
+// The root does not capture, but `Located` still fills its own field
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Uncaptured {
+    Located(Located),
+}
+
+#[amass_telety(crate::util::types, capture = backtrace)]
+#[derive(Debug)]
+pub(crate) enum Traced {
+    Wheat(Wheat, std::backtrace::Backtrace),
+}
+
+// Without `max_depth`, `Ale` would be reachable through both variants
+#[amass_telety(crate::util::types, max_depth = 2)]
+#[derive(Debug)]
+pub(crate) enum Round {
+    Pint(Pint),
+    Ale(Ale),
+}
+
+#[telety(crate::util::types)]
+pub(crate) trait Diagnostic {
+    fn code(&self) -> u32;
+
+    fn message(&self, prefix: &str) -> String {
+        format!("{prefix}: {}", self.code())
+    }
+
+    fn reset(&mut self);
+}
+
+#[derive(Debug)]
+pub(crate) struct IoError;
+
+impl Diagnostic for IoError {
+    fn code(&self) -> u32 {
+        1
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError {
+    pub(crate) line: u32,
+}
+
+impl Diagnostic for ParseError {
+    fn code(&self) -> u32 {
+        100 + self.line
+    }
+
+    fn message(&self, prefix: &str) -> String {
+        format!("{prefix}: line {}", self.line)
+    }
+
+    fn reset(&mut self) {
+        self.line = 0;
+    }
+}
+
+impl From<std::num::ParseIntError> for ParseError {
+    fn from(_: std::num::ParseIntError) -> Self {
+        Self { line: 1 }
+    }
+}
+
+impl From<std::num::ParseFloatError> for ParseError {
+    fn from(_: std::num::ParseFloatError) -> Self {
+        Self { line: 2 }
+    }
+}
+
+#[amass_telety(crate::util::types, delegate(Diagnostic))]
+#[derive(Debug)]
+pub(crate) enum InputError {
+    Io(IoError),
+    #[amass_action(also_from(std::num::ParseIntError, std::num::ParseFloatError))]
+    Parse(Box<ParseError>),
+}
+
+#[amass_telety(crate::util::types, delegate(crate::util::types::Diagnostic))]
+#[derive(Debug)]
+pub(crate) enum AppError {
+    Input(InputError),
+    // Delegated regardless of the variant action
+    #[amass_action(ignore)]
+    Context {
+        #[amass_field]
+        io: IoError,
+        context: &'static str,
+    },
+}
+
+// Recursive types are delegated without requiring themselves to implement the trait
+#[amass_telety(crate::util::types, on_cycle = ignore, delegate(Diagnostic))]
+#[derive(Debug)]
+pub(crate) enum ChainError {
+    Io(IoError),
+    Chain(Box<Self>),
+}
+
+#[amass_telety(crate::util::types, on_cycle = ignore, delegate(Diagnostic))]
+#[derive(Debug)]
+pub(crate) enum OuterError {
+    Parse(ParseError),
+    Inner(Box<InnerError>),
+}
+
+#[amass_telety(crate::util::types, on_cycle = ignore, delegate(Diagnostic))]
+#[derive(Debug)]
+pub(crate) enum InnerError {
+    Io(IoError),
+    Outer(Box<OuterError>),
+}
+
+impl std::fmt::Display for IoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("io error")
+    }
+}
+
+impl std::error::Error for IoError {}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "parse error on line {}", self.line)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[amass_telety(crate::util::types, error)]
+#[derive(Debug)]
+pub(crate) enum ConfigError {
+    #[amass_action(prefix = "reading config")]
+    Io(IoError),
+    Missing,
+    #[amass_action(prefix = "invalid range")]
+    Range(u32, u32),
+}
+
+#[amass_telety(crate::util::types, error)]
+#[derive(Debug)]
+pub(crate) enum StartupError {
+    Config(ConfigError),
+    #[amass_action(prefix = "parsing arguments")]
+    Parse(Box<ParseError>),
+}
+
+// The boxed variant is the error itself, which is displayed and is the source like any other
+#[amass_telety(crate::util::types, on_cycle = ignore, error)]
+#[derive(Debug)]
+pub(crate) enum RetryError {
+    Io(IoError),
+    #[amass_action(prefix = "retrying")]
+    Retry(Box<RetryError>),
+}
+
+#[derive(Debug)]
+pub(crate) struct IoContext {
+    pub(crate) error: std::io::Error,
+    pub(crate) retries: u32,
+}
+
+impl IoContext {
+    pub(crate) fn new(error: std::io::Error) -> Self {
+        Self { error, retries: 3 }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Label(pub(crate) String);
+
+impl Label {
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+#[amass_telety(crate::util::types, impls(From, TryFrom, AsLeaf, Leaf))]
+#[derive(Debug)]
+pub(crate) enum Fetch {
+    #[amass_action(with = IoContext::new, from = std::io::Error)]
+    Io(IoContext),
+    #[amass_action(with = Label::from_bytes, from = Vec<u8>)]
+    Label(Label),
+    Count(u32),
+}
+
+#[amass_telety(crate::util::types)]
+#[derive(Debug)]
+pub(crate) enum Task {
+    Fetch(Fetch),
+}
+
+// `cfg(any())` is never enabled, so the variant and everything generated for it is compiled out
+#[amass_telety(
+    crate::util::types,
+    kind = GatedKind,
+    conversions = CONVERSIONS,
+    explain,
+    delegate(Diagnostic),
+    error
+)]
+#[derive(Debug)]
+pub(crate) enum Gated {
+    Io(IoError),
+    #[cfg(any())]
+    Parse(ParseError),
+    #[deprecated(note = "use `Gated::Io`")]
+    Context(ParseError),
+}